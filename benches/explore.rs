@@ -0,0 +1,41 @@
+extern crate x86_litmus;
+
+use std::fs;
+use std::time::{Duration, Instant};
+use x86_litmus::config::Config;
+use x86_litmus::litmus_with_config;
+use x86_litmus::parser::parse;
+
+// Number of batches, and of checks of each test in a batch.
+const BATCHES: u32 = 20;
+const RUNS: u32 = 100;
+
+// Times the exploration of some of the larger litmus tests, reporting the
+// mean time of a check over the fastest batch, which is the least disturbed
+// by the rest of the machine. Run with `cargo bench`.
+fn main() {
+  for name in &["iriw", "n6", "amd5"] {
+    let path = format!("litmus/{}.litmus", name);
+    let test = parse(&fs::read_to_string(&path).unwrap()).unwrap();
+    let config = Config::default();
+    let mut best = Duration::MAX;
+    let mut states = 0;
+
+    for _ in 0..BATCHES {
+      let start = Instant::now();
+      for _ in 0..RUNS {
+        let result = litmus_with_config(
+          &test.name,
+          &test.prog,
+          test.init.clone(),
+          &test.pred,
+          test.pred_type,
+          &config,
+        ).unwrap();
+        states = result.states;
+      }
+      best = best.min(start.elapsed() / RUNS);
+    }
+    eprintln!("{}: {} states, {:?} per check", name, states, best);
+  }
+}
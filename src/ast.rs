@@ -1,47 +1,154 @@
 use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Mutex;
 
-#[derive(PartialEq, Eq, Hash, Clone, Copy)]
-pub struct Value(pub i32);
+// names of memory locations created at runtime, e.g. by the litmus parser.
+static NAMES: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
+
+// Returns a static copy of name, leaking at most one allocation per distinct
+// name.
+pub fn intern(name: &str) -> &'static str {
+  let mut names = NAMES.lock().unwrap();
 
-impl Default for Value {
-  fn default() -> Self {
-    Value(0)
+  if let Some(interned) = names.iter().find(|interned| **interned == name) {
+    return interned;
   }
+  let interned: &'static str = Box::leak(name.to_string().into_boxed_str());
+  names.push(interned);
+  interned
 }
 
-#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Default, Debug)]
+pub struct Value(pub i32);
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug)]
 pub struct Proc(pub u32);
 
-#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug)]
 pub struct MemLoc(pub &'static str);
 
-#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+impl MemLoc {
+  pub fn intern(name: &str) -> MemLoc {
+    MemLoc(intern(name))
+  }
+}
+
+// names of the locations of generated tests, taken in order.
+pub const MEMLOCS: [&str; 8] = ["x", "y", "z", "w", "u", "v", "s", "t"];
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug)]
 pub enum Reg {
   Eax,
   Ebx,
   Ecx,
   Edx,
 
-  // internal register, should not be used by user, only used in xchg
-  // instruction.
+  // internal register, should not be used by user, only used in desugared
+  // xchg and read-modify-write instructions.
   Internal,
 }
 
-#[derive(Clone, Copy)]
+// registers a test may use, taken in order by generated tests.
+pub const REGS: [Reg; 4] = [Reg::Eax, Reg::Ebx, Reg::Ecx, Reg::Edx];
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug)]
+pub struct Label(pub &'static str);
+
+impl Label {
+  pub fn intern(name: &str) -> Label {
+    Label(intern(name))
+  }
+}
+
+// Condition codes of conditional instructions, evaluated on the flags.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Cond {
+  // equal (ZF).
+  E,
+  // not equal (!ZF).
+  Ne,
+  // signed less (SF != OF).
+  L,
+  // signed less or equal (ZF || SF != OF).
+  Le,
+  // signed greater (!ZF && SF == OF).
+  G,
+  // signed greater or equal (SF == OF).
+  Ge,
+  // unsigned below (CF).
+  B,
+  // unsigned below or equal (CF || ZF).
+  Be,
+  // unsigned above (!CF && !ZF).
+  A,
+  // unsigned above or equal (!CF).
+  Ae,
+  // sign (SF).
+  S,
+  // not sign (!SF).
+  Ns,
+  // overflow (OF).
+  O,
+  // not overflow (!OF).
+  No,
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
 pub enum Operand {
   Imm(Value),
   Reg(Reg),
   MemLoc(MemLoc),
 }
 
-#[derive(Clone, Copy)]
+// Binary arithmetic and logic operations, dest = dest op src.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum AluOp {
+  Add,
+  Sub,
+  And,
+  Or,
+  Xor,
+}
+
+// Unary arithmetic operations, dest = op dest.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum UnaryOp {
+  Inc,
+  Dec,
+  Neg,
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
 pub enum Inst {
   Mov(Operand, Operand),
   Xchg(Operand, Operand),
   Mfence,
+  Alu(AluOp, Operand, Operand),
+  Unary(UnaryOp, Operand),
+  // exchange and add, dest = dest + src and src = old dest.
+  Xadd(Operand, Operand),
+  // compare eax with dest, if equal dest = src, otherwise eax = dest.
+  Cmpxchg(Operand, Operand),
+  // lock-prefixed variants, dest must be a memory location.
+  LockAlu(AluOp, Operand, Operand),
+  LockUnary(UnaryOp, Operand),
+  LockXadd(Operand, Operand),
+  LockCmpxchg(Operand, Operand),
+  // marks the position of the next instruction as a jump target.
+  Label(Label),
+  Jmp(Label),
+  Jcc(Cond, Label),
+  // set flags as for sub, discarding the result.
+  Cmp(Operand, Operand),
+  // set flags as for and, discarding the result.
+  Test(Operand, Operand),
+  // dest = 1 if the condition holds, 0 otherwise.
+  Setcc(Cond, Operand),
+  // dest = src if the condition holds, dest must be a register.
+  Cmovcc(Cond, Operand, Operand),
 }
 
-#[derive(Clone, Copy)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
 pub enum CoreInst {
   // read from memory to register.
   Read(Reg, MemLoc),
@@ -59,18 +166,71 @@ pub enum CoreInst {
   Lock,
   // unlock bus.
   Unlock,
+  // compute register with another register.
+  Alu1(AluOp, Reg, Reg),
+  // compute register with an immediate value.
+  Alu2(AluOp, Reg, Value),
+  // compute register in place.
+  Unary(UnaryOp, Reg),
+  // exchange and add between registers.
+  Xadd(Reg, Reg),
+  // compare eax with the first register, exchanging with the second register
+  // if equal.
+  Cmpxchg(Reg, Reg),
+  // compare register with another register.
+  Cmp1(Reg, Reg),
+  // compare register with an immediate value.
+  Cmp2(Reg, Value),
+  // test register with another register.
+  Test1(Reg, Reg),
+  // test register with an immediate value.
+  Test2(Reg, Value),
+  // set register to whether the condition holds.
+  Setcc(Cond, Reg),
+  // move from register to register if the condition holds.
+  Cmovcc(Cond, Reg, Reg),
+  // jump to instruction index, which may be one past the end.
+  Jmp(usize),
+  // jump to instruction index if the condition holds.
+  Jcc(Cond, usize),
 }
 
+#[derive(Clone, Debug)]
 pub struct Prog(pub BTreeMap<Proc, Vec<Inst>>);
+#[derive(Clone, Debug)]
 pub struct CoreProg(pub BTreeMap<Proc, Vec<CoreInst>>);
 
+// Operand of a comparison in a predicate.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Expr {
+  Reg(Proc, Reg),
+  MemLoc(MemLoc),
+  Value(Value),
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Rel {
+  Eq,
+  Ne,
+  Lt,
+  Le,
+  Gt,
+  Ge,
+}
+
 pub enum Pred {
   Reg(Proc, Reg, Value),
   MemLoc(MemLoc, Value),
   And(Vec<Pred>),
+  Or(Vec<Pred>),
+  Not(Box<Pred>),
+  Implies(Box<Pred>, Box<Pred>),
+  True,
+  False,
+  Cmp(Expr, Rel, Expr),
 }
 
-#[derive(Clone, Copy)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
 pub enum PredType {
   // every terminal state should not satisfy this predicate.
   Forbidden,
@@ -79,3 +239,282 @@ pub enum PredType {
   // at least one terminal state should satisfy this predicate.
   Allowed,
 }
+
+impl fmt::Display for Value {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}", self.0)
+  }
+}
+
+impl fmt::Display for Proc {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "P{}", self.0)
+  }
+}
+
+impl fmt::Display for MemLoc {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}", self.0)
+  }
+}
+
+impl fmt::Display for Reg {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let name = match *self {
+      Reg::Eax => "eax",
+      Reg::Ebx => "ebx",
+      Reg::Ecx => "ecx",
+      Reg::Edx => "edx",
+      Reg::Internal => "internal",
+    };
+    write!(f, "{}", name)
+  }
+}
+
+impl fmt::Display for AluOp {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let name = match *self {
+      AluOp::Add => "add",
+      AluOp::Sub => "sub",
+      AluOp::And => "and",
+      AluOp::Or => "or",
+      AluOp::Xor => "xor",
+    };
+    write!(f, "{}", name)
+  }
+}
+
+impl fmt::Display for UnaryOp {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let name = match *self {
+      UnaryOp::Inc => "inc",
+      UnaryOp::Dec => "dec",
+      UnaryOp::Neg => "neg",
+    };
+    write!(f, "{}", name)
+  }
+}
+
+impl fmt::Display for Label {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}", self.0)
+  }
+}
+
+impl fmt::Display for Cond {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let name = match *self {
+      Cond::E => "e",
+      Cond::Ne => "ne",
+      Cond::L => "l",
+      Cond::Le => "le",
+      Cond::G => "g",
+      Cond::Ge => "ge",
+      Cond::B => "b",
+      Cond::Be => "be",
+      Cond::A => "a",
+      Cond::Ae => "ae",
+      Cond::S => "s",
+      Cond::Ns => "ns",
+      Cond::O => "o",
+      Cond::No => "no",
+    };
+    write!(f, "{}", name)
+  }
+}
+
+impl fmt::Display for Operand {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Operand::Imm(value) => write!(f, "${}", value),
+      Operand::Reg(reg) => write!(f, "{}", reg),
+      Operand::MemLoc(memloc) => write!(f, "[{}]", memloc),
+    }
+  }
+}
+
+impl fmt::Display for Inst {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Inst::Mov(operand1, operand2) => {
+        write!(f, "mov {}, {}", operand1, operand2)
+      },
+      Inst::Xchg(operand1, operand2) => {
+        write!(f, "xchg {}, {}", operand1, operand2)
+      },
+      Inst::Mfence => write!(f, "mfence"),
+      Inst::Alu(op, operand1, operand2) => {
+        write!(f, "{} {}, {}", op, operand1, operand2)
+      },
+      Inst::Unary(op, operand) => write!(f, "{} {}", op, operand),
+      Inst::Xadd(operand1, operand2) => {
+        write!(f, "xadd {}, {}", operand1, operand2)
+      },
+      Inst::Cmpxchg(operand1, operand2) => {
+        write!(f, "cmpxchg {}, {}", operand1, operand2)
+      },
+      Inst::LockAlu(op, operand1, operand2) => {
+        write!(f, "lock {} {}, {}", op, operand1, operand2)
+      },
+      Inst::LockUnary(op, operand) => write!(f, "lock {} {}", op, operand),
+      Inst::LockXadd(operand1, operand2) => {
+        write!(f, "lock xadd {}, {}", operand1, operand2)
+      },
+      Inst::LockCmpxchg(operand1, operand2) => {
+        write!(f, "lock cmpxchg {}, {}", operand1, operand2)
+      },
+      Inst::Label(label) => write!(f, "{}:", label),
+      Inst::Jmp(label) => write!(f, "jmp {}", label),
+      Inst::Jcc(cond, label) => write!(f, "j{} {}", cond, label),
+      Inst::Cmp(operand1, operand2) => {
+        write!(f, "cmp {}, {}", operand1, operand2)
+      },
+      Inst::Test(operand1, operand2) => {
+        write!(f, "test {}, {}", operand1, operand2)
+      },
+      Inst::Setcc(cond, operand) => write!(f, "set{} {}", cond, operand),
+      Inst::Cmovcc(cond, operand1, operand2) => {
+        write!(f, "cmov{} {}, {}", cond, operand1, operand2)
+      },
+    }
+  }
+}
+
+impl fmt::Display for CoreInst {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      CoreInst::Read(reg, memloc) => write!(f, "mov {}, [{}]", reg, memloc),
+      CoreInst::Write1(memloc, reg) => write!(f, "mov [{}], {}", memloc, reg),
+      CoreInst::Write2(memloc, value) => {
+        write!(f, "mov [{}], ${}", memloc, value)
+      },
+      CoreInst::Mov1(reg1, reg2) => write!(f, "mov {}, {}", reg1, reg2),
+      CoreInst::Mov2(reg, value) => write!(f, "mov {}, ${}", reg, value),
+      CoreInst::Mfence => write!(f, "mfence"),
+      CoreInst::Lock => write!(f, "lock"),
+      CoreInst::Unlock => write!(f, "unlock"),
+      CoreInst::Alu1(op, reg1, reg2) => write!(f, "{} {}, {}", op, reg1, reg2),
+      CoreInst::Alu2(op, reg, value) => {
+        write!(f, "{} {}, ${}", op, reg, value)
+      },
+      CoreInst::Unary(op, reg) => write!(f, "{} {}", op, reg),
+      CoreInst::Xadd(reg1, reg2) => write!(f, "xadd {}, {}", reg1, reg2),
+      CoreInst::Cmpxchg(reg1, reg2) => {
+        write!(f, "cmpxchg {}, {}", reg1, reg2)
+      },
+      CoreInst::Cmp1(reg1, reg2) => write!(f, "cmp {}, {}", reg1, reg2),
+      CoreInst::Cmp2(reg, value) => write!(f, "cmp {}, ${}", reg, value),
+      CoreInst::Test1(reg1, reg2) => write!(f, "test {}, {}", reg1, reg2),
+      CoreInst::Test2(reg, value) => write!(f, "test {}, ${}", reg, value),
+      CoreInst::Setcc(cond, reg) => write!(f, "set{} {}", cond, reg),
+      CoreInst::Cmovcc(cond, reg1, reg2) => {
+        write!(f, "cmov{} {}, {}", cond, reg1, reg2)
+      },
+      CoreInst::Jmp(target) => write!(f, "jmp {}", target),
+      CoreInst::Jcc(cond, target) => write!(f, "j{} {}", cond, target),
+    }
+  }
+}
+
+impl AluOp {
+  pub fn apply(self, lhs: Value, rhs: Value) -> Value {
+    match self {
+      AluOp::Add => Value(lhs.0.wrapping_add(rhs.0)),
+      AluOp::Sub => Value(lhs.0.wrapping_sub(rhs.0)),
+      AluOp::And => Value(lhs.0 & rhs.0),
+      AluOp::Or => Value(lhs.0 | rhs.0),
+      AluOp::Xor => Value(lhs.0 ^ rhs.0),
+    }
+  }
+}
+
+impl UnaryOp {
+  pub fn apply(self, value: Value) -> Value {
+    match self {
+      UnaryOp::Inc => Value(value.0.wrapping_add(1)),
+      UnaryOp::Dec => Value(value.0.wrapping_sub(1)),
+      UnaryOp::Neg => Value(value.0.wrapping_neg()),
+    }
+  }
+}
+
+impl Rel {
+  pub fn holds(self, lhs: Value, rhs: Value) -> bool {
+    match self {
+      Rel::Eq => lhs == rhs,
+      Rel::Ne => lhs != rhs,
+      Rel::Lt => lhs < rhs,
+      Rel::Le => lhs <= rhs,
+      Rel::Gt => lhs > rhs,
+      Rel::Ge => lhs >= rhs,
+    }
+  }
+}
+
+impl fmt::Display for Expr {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Expr::Reg(processor, reg) => write!(f, "{}:{}", processor.0, reg),
+      Expr::MemLoc(memloc) => write!(f, "{}", memloc),
+      Expr::Value(value) => write!(f, "{}", value),
+    }
+  }
+}
+
+impl fmt::Display for Rel {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let op = match *self {
+      Rel::Eq => "=",
+      Rel::Ne => "!=",
+      Rel::Lt => "<",
+      Rel::Le => "<=",
+      Rel::Gt => ">",
+      Rel::Ge => ">=",
+    };
+    write!(f, "{}", op)
+  }
+}
+
+fn fmt_preds(f: &mut fmt::Formatter, preds: &[Pred], op: &str) -> fmt::Result {
+  write!(f, "(")?;
+  for (i, pred) in preds.iter().enumerate() {
+    if i > 0 {
+      write!(f, " {} ", op)?;
+    }
+    write!(f, "{}", pred)?;
+  }
+  write!(f, ")")
+}
+
+// Printed in herd syntax, e.g. `(0:eax=1 /\ x=0)`.
+impl fmt::Display for Pred {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Pred::Reg(processor, reg, value) => {
+        write!(f, "{}:{}={}", processor.0, reg, value)
+      },
+      Pred::MemLoc(memloc, value) => write!(f, "{}={}", memloc, value),
+      Pred::And(ref preds) => fmt_preds(f, preds, "/\\"),
+      Pred::Or(ref preds) => fmt_preds(f, preds, "\\/"),
+      Pred::Not(ref pred) => write!(f, "~{}", pred),
+      Pred::Implies(ref pred1, ref pred2) => {
+        write!(f, "({} => {})", pred1, pred2)
+      },
+      Pred::True => write!(f, "true"),
+      Pred::False => write!(f, "false"),
+      Pred::Cmp(expr1, rel, expr2) => write!(f, "{}{}{}", expr1, rel, expr2),
+    }
+  }
+}
+
+// Printed as the corresponding herd quantifier.
+impl fmt::Display for PredType {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let quantifier = match *self {
+      PredType::Forbidden => "~exists",
+      PredType::Required => "forall",
+      PredType::Allowed => "exists",
+    };
+    write!(f, "{}", quantifier)
+  }
+}
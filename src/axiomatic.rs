@@ -0,0 +1,464 @@
+use ast::{CoreProg, CoreInst, Proc, MemLoc, Value};
+use config::{Config, Bound};
+use run::{mov, alu, branch};
+use state::{State, ProcState, Terminal};
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashSet;
+
+// Backward jumps unrolled per processor unless Config::max_iterations says
+// otherwise, as candidate executions of a loop are otherwise unbounded.
+pub const UNROLL: usize = 2;
+
+// Write observed by a read.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+enum Source {
+  // initial value of the location.
+  Init,
+  // event index of a write in the thread of a processor.
+  Write(Proc, usize),
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+enum Kind {
+  Read(MemLoc, Value, Source),
+  Write(MemLoc, Value),
+  Fence,
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+struct Event {
+  kind: Kind,
+  // index of the locked section containing the event on its processor.
+  section: Option<usize>,
+}
+
+impl Event {
+  fn memloc(&self) -> Option<MemLoc> {
+    match self.kind {
+      Kind::Read(memloc, _, _) | Kind::Write(memloc, _) => Some(memloc),
+      Kind::Fence => None,
+    }
+  }
+
+  fn is_write(&self) -> bool {
+    matches!(self.kind, Kind::Write(..))
+  }
+}
+
+// Events of a processor in program order, with the register state reached
+// by executing them.
+#[derive(PartialEq, Eq, Hash, Clone)]
+struct Thread {
+  state: ProcState,
+  events: Vec<Event>,
+  // number of locked sections entered so far.
+  sections: usize,
+  locked: bool,
+}
+
+// A candidate execution, possibly not complete: the events of every
+// processor, each read with the write it reads from.
+type Candidate = BTreeMap<Proc, Thread>;
+
+// Outcomes of a program under the axiomatic x86-TSO model.
+pub struct Axiomatic {
+  // distinct outcomes, in ascending order.
+  pub outcomes: Vec<Terminal>,
+  // number of complete candidate executions, counting each coherence order.
+  pub candidates: usize,
+  // number of those satisfying the axioms.
+  pub consistent: usize,
+  // bounds that cut some candidate short.
+  pub truncated: Vec<Bound>,
+}
+
+fn advance(state: &mut ProcState, len: usize) {
+  state.ip = state.ip.map(|ip| ip + 1).filter(|&ip| ip < len);
+}
+
+// Candidates extending candidate by the next instruction of processor, one
+// for each write a read may read from. Reads choose among writes already in
+// the candidate, which loses no execution as po ∪ rf is acyclic under TSO.
+fn extend(
+  prog: &CoreProg,
+  init: &State,
+  candidate: &Candidate,
+  processor: Proc,
+  unroll: usize,
+) -> Result<Vec<Candidate>, Bound> {
+  let thread = &candidate[&processor];
+  let proc_prog = &prog.0[&processor];
+  let ip = match thread.state.ip {
+    Some(ip) => ip,
+    None => return Ok(vec![]),
+  };
+  let section = if thread.locked {
+    Some(thread.sections - 1)
+  } else {
+    None
+  };
+  // candidate with processor stepped past the current instruction,
+  // recording event if any.
+  let step = |event: Option<Kind>| -> Candidate {
+    let mut ncandidate = candidate.clone();
+    {
+      let nthread = ncandidate.get_mut(&processor).unwrap();
+      if let Some(kind) = event {
+        nthread.events.push(Event { kind, section });
+      }
+      advance(&mut nthread.state, proc_prog.len());
+    }
+    ncandidate
+  };
+
+  let ncandidates = match proc_prog[ip] {
+    CoreInst::Read(reg, memloc) => {
+      let mut sources = vec![Source::Init];
+      for (writer, wthread) in candidate {
+        for (index, event) in wthread.events.iter().enumerate() {
+          if event.is_write() && event.memloc() == Some(memloc) {
+            sources.push(Source::Write(*writer, index));
+          }
+        }
+      }
+      sources
+        .into_iter()
+        .map(|source| {
+          let value = match source {
+            Source::Init => init.mem.get(&memloc).cloned().unwrap_or_default(),
+            Source::Write(writer, index) => {
+              match candidate[&writer].events[index].kind {
+                Kind::Write(_, value) => value,
+                _ => unreachable!(),
+              }
+            },
+          };
+          let mut ncandidate = step(Some(Kind::Read(memloc, value, source)));
+          ncandidate.get_mut(&processor).unwrap().state.set(reg, value);
+          ncandidate
+        })
+        .collect()
+    },
+    CoreInst::Write1(memloc, reg) => {
+      vec![step(Some(Kind::Write(memloc, thread.state.get(reg))))]
+    },
+    CoreInst::Write2(memloc, value) => {
+      vec![step(Some(Kind::Write(memloc, value)))]
+    },
+    CoreInst::Mfence => vec![step(Some(Kind::Fence))],
+    CoreInst::Lock | CoreInst::Unlock => {
+      let mut ncandidate = step(None);
+      {
+        let nthread = ncandidate.get_mut(&processor).unwrap();
+        nthread.locked = proc_prog[ip] == CoreInst::Lock;
+        if nthread.locked {
+          nthread.sections += 1;
+        }
+      }
+      vec![ncandidate]
+    },
+    _ => {
+      // register instructions run as under the operational model.
+      let mut state = State::new(&vec![processor]);
+      state.procs.insert(processor, thread.state.clone());
+      let nstate = mov(processor, prog, &state)
+        .or_else(|| alu(processor, prog, &state))
+        .or_else(|| branch(processor, prog, &state))
+        .unwrap();
+      let mut nproc_state = nstate.procs[&processor].clone();
+      if nproc_state.ip.is_some() && nproc_state.ip <= thread.state.ip {
+        nproc_state.iterations += 1;
+        if nproc_state.iterations > unroll {
+          return Err(Bound::Iterations);
+        }
+      }
+      let mut ncandidate = candidate.clone();
+      ncandidate.get_mut(&processor).unwrap().state = nproc_state;
+      vec![ncandidate]
+    },
+  };
+  Ok(ncandidates)
+}
+
+// All orders of items.
+fn permutations(items: &[usize]) -> Vec<Vec<usize>> {
+  if items.is_empty() {
+    return vec![vec![]];
+  }
+  let mut orders = vec![];
+  for i in 0..items.len() {
+    let mut rest = items.to_vec();
+    let first = rest.remove(i);
+    for mut order in permutations(&rest) {
+      order.insert(0, first);
+      orders.push(order);
+    }
+  }
+  orders
+}
+
+fn is_acyclic(nodes: usize, edges: &[(usize, usize)]) -> bool {
+  let mut indegree = vec![0; nodes];
+  let mut successors = vec![vec![]; nodes];
+  for &(from, to) in edges {
+    indegree[to] += 1;
+    successors[from].push(to);
+  }
+  let mut ready: Vec<usize> =
+    (0..nodes).filter(|&node| indegree[node] == 0).collect();
+  let mut visited = 0;
+  while let Some(node) = ready.pop() {
+    visited += 1;
+    for &next in &successors[node] {
+      indegree[next] -= 1;
+      if indegree[next] == 0 {
+        ready.push(next);
+      }
+    }
+  }
+  visited == nodes
+}
+
+// Memory event of an execution, init writes have no processor.
+struct Flat {
+  processor: Option<Proc>,
+  event: Event,
+}
+
+// Checks every coherence order of the complete candidate against the
+// axioms, adding the outcomes of consistent executions.
+fn check(
+  candidate: &Candidate,
+  init: &State,
+  outcomes: &mut BTreeSet<Terminal>,
+  result: &mut Axiomatic,
+) {
+  let mut memlocs: BTreeSet<MemLoc> = init.mem.keys().cloned().collect();
+  for thread in candidate.values() {
+    memlocs.extend(thread.events.iter().filter_map(|event| event.memloc()));
+  }
+
+  let mut events: Vec<Flat> = memlocs
+    .iter()
+    .map(|&memloc| {
+      let value = init.mem.get(&memloc).cloned().unwrap_or_default();
+      Flat {
+        processor: None,
+        event: Event { kind: Kind::Write(memloc, value), section: None },
+      }
+    })
+    .collect();
+  let init_id = |memloc: MemLoc| memlocs.iter().position(|&m| m == memloc);
+  // id of the first event of each processor.
+  let mut first: BTreeMap<Proc, usize> = BTreeMap::new();
+  for (processor, thread) in candidate {
+    first.insert(*processor, events.len());
+    for event in &thread.events {
+      events.push(Flat { processor: Some(*processor), event: *event });
+    }
+  }
+  let id = |source: Source, memloc: MemLoc| match source {
+    Source::Init => init_id(memloc).unwrap(),
+    Source::Write(processor, index) => first[&processor] + index,
+  };
+
+  // locked sections collapse into a single node of the ordering graph.
+  let mut sections: BTreeMap<(Proc, usize), usize> = BTreeMap::new();
+  let node: Vec<usize> = (0..events.len())
+    .map(|e| match (events[e].processor, events[e].event.section) {
+      (Some(processor), Some(section)) => {
+        let next = events.len() + sections.len();
+        *sections.entry((processor, section)).or_insert(next)
+      },
+      _ => e,
+    })
+    .collect();
+  let nodes = events.len() + sections.len();
+
+  // program order within each processor, as pairs of event ids.
+  let mut po: Vec<(usize, usize)> = vec![];
+  for (processor, thread) in candidate {
+    let base = first[processor];
+    for i in 0..thread.events.len() {
+      for j in i + 1..thread.events.len() {
+        po.push((base + i, base + j));
+      }
+    }
+  }
+  let mut rf: Vec<(usize, usize)> = vec![];
+  for (e, flat) in events.iter().enumerate() {
+    if let Kind::Read(memloc, _, source) = flat.event.kind {
+      rf.push((id(source, memloc), e));
+    }
+  }
+
+  // writes to each location other than its init write.
+  let writes: Vec<Vec<usize>> = memlocs
+    .iter()
+    .map(|&memloc| {
+      (memlocs.len()..events.len())
+        .filter(|&e| {
+          events[e].event.is_write() && events[e].event.memloc() == Some(memloc)
+        })
+        .collect()
+    })
+    .collect();
+  let mut orders: Vec<Vec<Vec<usize>>> = vec![vec![]];
+  for (i, ws) in writes.iter().enumerate() {
+    let mut norders = vec![];
+    for order in &orders {
+      for perm in permutations(ws) {
+        let mut norder = order.clone();
+        let mut chain = vec![i];
+        chain.extend(perm);
+        norder.push(chain);
+        norders.push(norder);
+      }
+    }
+    orders = norders;
+  }
+
+  for co_order in orders {
+    result.candidates += 1;
+
+    let mut co: Vec<(usize, usize)> = vec![];
+    for chain in &co_order {
+      for i in 0..chain.len() {
+        for j in i + 1..chain.len() {
+          co.push((chain[i], chain[j]));
+        }
+      }
+    }
+    let mut fr: Vec<(usize, usize)> = vec![];
+    for &(w, r) in &rf {
+      for &(w1, w2) in &co {
+        if w1 == w {
+          fr.push((r, w2));
+        }
+      }
+    }
+
+    // sc per location: po restricted to one location, rf, co and fr.
+    let same_memloc = |&&(a, b): &&(usize, usize)| {
+      events[a].event.memloc().is_some() &&
+        events[a].event.memloc() == events[b].event.memloc()
+    };
+    let mut uniproc: Vec<(usize, usize)> =
+      po.iter().filter(same_memloc).cloned().collect();
+    uniproc.extend(&rf);
+    uniproc.extend(&co);
+    uniproc.extend(&fr);
+    if !is_acyclic(events.len(), &uniproc) {
+      continue;
+    }
+
+    // tso: preserved program order, where a write may pass a later read
+    // unless a fence or a locked instruction separates them, plus external
+    // reads-from, coherence and from-reads.
+    let mut ghb: Vec<(usize, usize)> = vec![];
+    for &(a, b) in &po {
+      let (ea, eb) = (events[a].event, events[b].event);
+      if ea.kind == Kind::Fence || eb.kind == Kind::Fence {
+        continue;
+      }
+      let fenced = ea.section.is_some() || eb.section.is_some() ||
+        (a..b).any(|e| events[e].event.kind == Kind::Fence);
+      if !ea.is_write() || eb.is_write() || fenced {
+        ghb.push((a, b));
+      }
+    }
+    ghb.extend(rf.iter().filter(|&&(w, r)| {
+      events[w].processor != events[r].processor
+    }));
+    ghb.extend(&co);
+    ghb.extend(&fr);
+    let ghb: Vec<(usize, usize)> = ghb
+      .into_iter()
+      .map(|(a, b)| (node[a], node[b]))
+      .filter(|&(a, b)| a != b)
+      .collect();
+    if !is_acyclic(nodes, &ghb) {
+      continue;
+    }
+
+    result.consistent += 1;
+    let mut mem = init.mem.to_map();
+    // locations that are only read keep their initial value, if any.
+    for (memloc, chain) in memlocs.iter().zip(&co_order) {
+      if let Kind::Write(_, value) = events[*chain.last().unwrap()].event.kind {
+        if chain.len() > 1 {
+          mem.insert(*memloc, value);
+        }
+      }
+    }
+    let procs = candidate
+      .iter()
+      .map(|(processor, thread)| {
+        (*processor, thread.state.finalize().unwrap())
+      })
+      .collect();
+    outcomes.insert(Terminal { procs, mem });
+  }
+}
+
+// Enumerates the candidate executions of prog from init and keeps those
+// consistent with the axiomatic x86-TSO model.
+pub fn axiomatic(prog: &CoreProg, init: &State, config: &Config) -> Axiomatic {
+  let unroll = config.max_iterations.unwrap_or(UNROLL);
+  let mut result = Axiomatic {
+    outcomes: vec![],
+    candidates: 0,
+    consistent: 0,
+    truncated: vec![],
+  };
+  let mut outcomes: BTreeSet<Terminal> = BTreeSet::new();
+  let mut truncated: BTreeSet<Bound> = BTreeSet::new();
+
+  let mut start: Candidate = BTreeMap::new();
+  for (processor, proc_state) in &init.procs {
+    let mut state = proc_state.clone();
+    if prog.0.get(processor).map_or(true, |insts| insts.is_empty()) {
+      state.ip = None;
+    }
+    start.insert(*processor, Thread {
+      state,
+      events: vec![],
+      sections: 0,
+      locked: false,
+    });
+  }
+  let mut visited: HashSet<Candidate> = HashSet::new();
+  let mut stack = vec![start.clone()];
+  visited.insert(start);
+
+  while let Some(candidate) = stack.pop() {
+    let running: Vec<Proc> = candidate
+      .iter()
+      .filter(|&(_, thread)| thread.state.ip.is_some())
+      .map(|(processor, _)| *processor)
+      .collect();
+    if running.is_empty() {
+      check(&candidate, init, &mut outcomes, &mut result);
+      continue;
+    }
+    for processor in running {
+      match extend(prog, init, &candidate, processor, unroll) {
+        Ok(ncandidates) => {
+          for ncandidate in ncandidates {
+            if visited.insert(ncandidate.clone()) {
+              stack.push(ncandidate);
+            }
+          }
+        },
+        Err(bound) => {
+          truncated.insert(bound);
+        },
+      }
+    }
+  }
+
+  result.outcomes = outcomes.into_iter().collect();
+  result.truncated = truncated.into_iter().collect();
+  result
+}
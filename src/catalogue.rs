@@ -0,0 +1,395 @@
+use check::CheckResult;
+use config::Config;
+use litmus_with_config;
+use model::{MemoryModel, Sc};
+use parser::Litmus;
+
+// A named test of the catalogue with its expected verdicts.
+pub struct Entry {
+  pub name: &'static str,
+  // where the test comes from and what it shows.
+  pub description: &'static str,
+  pub test: fn() -> Litmus,
+  // whether the test meets its condition under x86-TSO and under SC.
+  pub tso: bool,
+  pub sc: bool,
+}
+
+// Result of checking an entry of the catalogue.
+pub struct Run {
+  pub entry: &'static Entry,
+  pub result: CheckResult,
+  // the verdict the entry expects under the model it was checked with.
+  pub expected: bool,
+}
+
+impl Entry {
+  // expected verdict under model, SC or else x86-TSO.
+  pub fn expected(&self, model: &dyn MemoryModel) -> bool {
+    if model.name() == Sc.name() {
+      self.sc
+    } else {
+      self.tso
+    }
+  }
+
+  pub fn check(&'static self, config: &Config) -> Run {
+    let test = (self.test)();
+    let result = litmus_with_config(
+      &test.name,
+      &test.prog,
+      test.init,
+      &test.pred,
+      test.pred_type,
+      config,
+    ).unwrap();
+    Run { entry: self, result, expected: self.expected(config.model) }
+  }
+}
+
+impl Run {
+  // whether the verdict is the expected one.
+  pub fn passed(&self) -> bool {
+    self.result.verdict == self.expected
+  }
+}
+
+// Checks every test of the catalogue, in order.
+pub fn check_all(config: &Config) -> Vec<Run> {
+  CATALOGUE.iter().map(|entry| entry.check(config)).collect()
+}
+
+pub fn find(name: &str) -> Option<&'static Entry> {
+  CATALOGUE.iter().find(|entry| entry.name == name)
+}
+
+// Intel SDM 8.2.3.2: stores are not reordered with other stores and loads
+// with other loads, so message passing works.
+pub fn ex1() -> Litmus {
+  litmus! {
+    ex1 { x = 0; y = 0; }
+    P0: mov [x], $1; mov [y], $1
+    | P1: mov eax, [y]; mov ebx, [x]
+    ~exists (1:eax = 1 && 1:ebx = 0)
+  }
+}
+
+// Intel SDM 8.2.3.3: stores are not reordered with older loads.
+pub fn ex2() -> Litmus {
+  litmus! {
+    ex2 { x = 0; y = 0; }
+    P0: mov eax, [x]; mov [y], $1
+    | P1: mov ebx, [y]; mov [x], $1
+    ~exists (0:eax = 1 && 1:ebx = 1)
+  }
+}
+
+// Intel SDM 8.2.3.4: loads may be reordered with older stores to other
+// locations, store buffering.
+pub fn ex3() -> Litmus {
+  litmus! {
+    ex3 { x = 0; y = 0; }
+    P0: mov [x], $1; mov eax, [y]
+    | P1: mov [y], $1; mov ebx, [x]
+    exists (0:eax = 0 && 1:ebx = 0)
+  }
+}
+
+// Intel SDM 8.2.3.4: loads are not reordered with older stores to the same
+// location.
+pub fn ex4() -> Litmus {
+  litmus! {
+    ex4 { x = 0; }
+    P0: mov [x], $1; mov eax, [x]
+    forall (0:eax = 1)
+  }
+}
+
+// Intel SDM 8.2.3.5: intra-processor forwarding, each processor reads its
+// own write before the other sees it.
+pub fn ex5() -> Litmus {
+  litmus! {
+    ex5 { x = 0; y = 0; }
+    P0: mov [x], $1; mov eax, [x]; mov ebx, [y]
+    | P1: mov [y], $1; mov ecx, [y]; mov edx, [x]
+    exists (0:eax = 1 && 0:ebx = 0 && 1:ecx = 1 && 1:edx = 0)
+  }
+}
+
+// Intel SDM 8.2.3.6: stores are transitively visible.
+pub fn ex6() -> Litmus {
+  litmus! {
+    ex6 { x = 0; y = 0; }
+    P0: mov [x], $1
+    | P1: mov eax, [x]; mov [y], $1
+    | P2: mov ebx, [y]; mov ecx, [x]
+    ~exists (1:eax = 1 && 2:ebx = 1 && 2:ecx = 0)
+  }
+}
+
+// Intel SDM 8.2.3.7: stores are seen in a consistent order by other
+// processors, independent reads of independent writes.
+pub fn ex7() -> Litmus {
+  litmus! {
+    ex7 { x = 0; y = 0; }
+    P0: mov [x], $1
+    | P1: mov [y], $1
+    | P2: mov eax, [x]; mov ebx, [y]
+    | P3: mov ecx, [y]; mov edx, [x]
+    ~exists (2:eax = 1 && 2:ebx = 0 && 3:ecx = 1 && 3:edx = 0)
+  }
+}
+
+// Intel SDM 8.2.3.8: locked instructions have a total order.
+pub fn ex8() -> Litmus {
+  litmus! {
+    ex8 { 0:eax = 1; 1:ebx = 1; }
+    P0: xchg [x], eax
+    | P1: xchg [y], ebx
+    | P2: mov eax, [x]; mov ebx, [y]
+    | P3: mov ecx, [y]; mov edx, [x]
+    ~exists (2:eax = 1 && 2:ebx = 0 && 3:ecx = 1 && 3:edx = 0)
+  }
+}
+
+// Intel SDM 8.2.3.9: loads are not reordered with locks.
+pub fn ex9() -> Litmus {
+  litmus! {
+    ex9 { 0:eax = 1; 1:ecx = 1; }
+    P0: xchg [x], eax; mov ebx, [y]
+    | P1: xchg [y], ecx; mov edx, [x]
+    ~exists (0:ebx = 0 && 1:edx = 0)
+  }
+}
+
+// Intel SDM 8.2.3.9: stores are not reordered with locks.
+pub fn ex10() -> Litmus {
+  litmus! {
+    ex10 { 0:eax = 1; }
+    P0: xchg [x], eax; mov [y], $1
+    | P1: mov ebx, [y]; mov ecx, [x]
+    ~exists (1:ebx = 1 && 1:ecx = 0)
+  }
+}
+
+// AMD APM 7.2: loads may pass older stores to other locations even when
+// each processor stores twice, so each reads the other's first store.
+pub fn amd3() -> Litmus {
+  litmus! {
+    amd3 { x = 0; y = 0; }
+    P0: mov [x], $1; mov [x], $2; mov eax, [y]
+    | P1: mov [y], $1; mov [y], $2; mov ebx, [x]
+    exists (0:eax = 1 && 1:ebx = 1)
+  }
+}
+
+// AMD APM 7.2: mfence forbids store buffering.
+pub fn amd5() -> Litmus {
+  litmus! {
+    amd5 { x = 0; y = 0; }
+    P0: mov [x], $1; mfence; mov eax, [y]
+    | P1: mov [y], $1; mfence; mov ebx, [x]
+    ~exists (0:eax = 0 && 1:ebx = 0)
+  }
+}
+
+// x86-TSO paper: store buffering, the outcome SC forbids.
+pub fn sb() -> Litmus {
+  litmus! {
+    SB { x = 0; y = 0; }
+    P0: mov [x], $1; mov eax, [y]
+    | P1: mov [y], $1; mov ebx, [x]
+    exists (0:eax = 0 && 1:ebx = 0)
+  }
+}
+
+// x86-TSO paper: independent readers agree on the order of independent
+// writes.
+pub fn iriw() -> Litmus {
+  litmus! {
+    IRIW { x = 0; y = 0; }
+    P0: mov [x], $1
+    | P1: mov [y], $1
+    | P2: mov eax, [x]; mov ebx, [y]
+    | P3: mov ecx, [y]; mov edx, [x]
+    ~exists (2:eax = 1 && 2:ebx = 0 && 3:ecx = 1 && 3:edx = 0)
+  }
+}
+
+// x86-TSO paper: writes to one location are seen in a single order.
+pub fn n4b() -> Litmus {
+  litmus! {
+    n4b { x = 0; }
+    P0: mov eax, [x]; mov [x], $1
+    | P1: mov ecx, [x]; mov [x], $2
+    ~exists (0:eax = 2 && 1:ecx = 1)
+  }
+}
+
+// x86-TSO paper: processors do not read each other's writes to one
+// location in opposite orders.
+pub fn n5() -> Litmus {
+  litmus! {
+    n5 { x = 0; }
+    P0: mov [x], $1; mov eax, [x]
+    | P1: mov [x], $2; mov ebx, [x]
+    ~exists (0:eax = 2 && 1:ebx = 1)
+  }
+}
+
+// x86-TSO paper: a processor may read its own write before it becomes
+// globally visible.
+pub fn n6() -> Litmus {
+  litmus! {
+    n6 { x = 0; y = 0; }
+    P0: mov [x], $1; mov eax, [x]; mov ebx, [y]
+    | P1: mov [y], $2; mov [x], $2
+    exists (0:eax = 1 && 0:ebx = 0 && x = 1)
+  }
+}
+
+// x86-TSO paper: a processor may read its own write early, while a third
+// processor sees a later write but not that one.
+pub fn n7() -> Litmus {
+  litmus! {
+    n7 { x = 0; y = 0; }
+    P0: mov [x], $1; mov eax, [x]; mov ebx, [y]
+    | P1: mov [y], $1
+    | P2: mov ecx, [y]; mov edx, [x]
+    exists (0:eax = 1 && 0:ebx = 0 && 2:ecx = 1 && 2:edx = 0)
+  }
+}
+
+// Every test of the catalogue.
+pub static CATALOGUE: &[Entry] = &[
+  Entry {
+    name: "ex1",
+    description: "Intel: stores are not reordered with stores, nor loads \
+                  with loads",
+    test: ex1,
+    tso: true,
+    sc: true,
+  },
+  Entry {
+    name: "ex2",
+    description: "Intel: stores are not reordered with older loads",
+    test: ex2,
+    tso: true,
+    sc: true,
+  },
+  Entry {
+    name: "ex3",
+    description: "Intel: loads may be reordered with older stores",
+    test: ex3,
+    tso: true,
+    sc: false,
+  },
+  Entry {
+    name: "ex4",
+    description: "Intel: loads are not reordered with older stores to the \
+                  same location",
+    test: ex4,
+    tso: true,
+    sc: true,
+  },
+  Entry {
+    name: "ex5",
+    description: "Intel: intra-processor forwarding is allowed",
+    test: ex5,
+    tso: true,
+    sc: false,
+  },
+  Entry {
+    name: "ex6",
+    description: "Intel: stores are transitively visible",
+    test: ex6,
+    tso: true,
+    sc: true,
+  },
+  Entry {
+    name: "ex7",
+    description: "Intel: stores are seen in a consistent order",
+    test: ex7,
+    tso: true,
+    sc: true,
+  },
+  Entry {
+    name: "ex8",
+    description: "Intel: locked instructions have a total order",
+    test: ex8,
+    tso: true,
+    sc: true,
+  },
+  Entry {
+    name: "ex9",
+    description: "Intel: loads are not reordered with locks",
+    test: ex9,
+    tso: true,
+    sc: true,
+  },
+  Entry {
+    name: "ex10",
+    description: "Intel: stores are not reordered with locks",
+    test: ex10,
+    tso: true,
+    sc: true,
+  },
+  Entry {
+    name: "amd3",
+    description: "AMD: loads may pass older stores, each processor storing \
+                  twice",
+    test: amd3,
+    tso: true,
+    sc: false,
+  },
+  Entry {
+    name: "amd5",
+    description: "AMD: mfence forbids store buffering",
+    test: amd5,
+    tso: true,
+    sc: true,
+  },
+  Entry {
+    name: "SB",
+    description: "x86-TSO: store buffering",
+    test: sb,
+    tso: true,
+    sc: false,
+  },
+  Entry {
+    name: "IRIW",
+    description: "x86-TSO: independent reads of independent writes",
+    test: iriw,
+    tso: true,
+    sc: true,
+  },
+  Entry {
+    name: "n4b",
+    description: "x86-TSO: writes to one location are totally ordered",
+    test: n4b,
+    tso: true,
+    sc: true,
+  },
+  Entry {
+    name: "n5",
+    description: "x86-TSO: reads of one location agree with coherence",
+    test: n5,
+    tso: true,
+    sc: true,
+  },
+  Entry {
+    name: "n6",
+    description: "x86-TSO: reads may see the processor's own buffered write",
+    test: n6,
+    tso: true,
+    sc: false,
+  },
+  Entry {
+    name: "n7",
+    description: "x86-TSO: an early read of one's own write is not seen by \
+                  others",
+    test: n7,
+    tso: true,
+    sc: false,
+  },
+];
@@ -0,0 +1,317 @@
+use ast::{Pred, PredType};
+use config::Bound;
+use state::Terminal;
+use std::fmt;
+use trace::Trace;
+
+// Outcome of checking a litmus test.
+pub struct CheckResult {
+  pub name: String,
+  // distinct terminal outcomes, in ascending order.
+  pub outcomes: Vec<Terminal>,
+  // outcomes satisfying the predicate.
+  pub matching: Vec<Terminal>,
+  pub pred: String,
+  pub pred_type: PredType,
+  // number of distinct states explored.
+  pub states: usize,
+  // number of transitions taken during exploration.
+  pub transitions: usize,
+  // bounds that cut exploration short, in which case outcomes may be missing.
+  pub truncated: Vec<Bound>,
+  // whether the outcomes meet the expectation given by pred_type. Only an
+  // Allowed test can pass a truncated exploration, since the outcomes it
+  // missed might break a Forbidden or Required one.
+  pub verdict: bool,
+  // a terminal deciding the verdict with a shortest trace reaching it: one
+  // satisfying the predicate for Forbidden and Allowed, one violating it for
+  // Required.
+  pub witness: Option<(Terminal, Trace)>,
+  // estimated probability that a fingerprint collision hid some states, None
+  // unless states were remembered by fingerprints.
+  pub collision: Option<f64>,
+}
+
+impl CheckResult {
+  pub fn new(
+    name: &str,
+    mut terminals: Vec<(Terminal, Trace)>,
+    pred: &Pred,
+    pred_type: PredType,
+    states: usize,
+    transitions: usize,
+    truncated: Vec<Bound>,
+  ) -> Self {
+    // the sort is stable, so the shortest trace of each outcome survives.
+    terminals.sort_by(|a, b| a.0.cmp(&b.0));
+    terminals.dedup_by(|a, b| a.0 == b.0);
+
+    let witness = terminals
+      .iter()
+      .find(|&(terminal, _)| match pred_type {
+        PredType::Forbidden | PredType::Allowed => terminal.satisfy(pred),
+        PredType::Required => !terminal.satisfy(pred),
+      })
+      .cloned();
+    let outcomes: Vec<Terminal> =
+      terminals.into_iter().map(|(terminal, _)| terminal).collect();
+
+    let matching: Vec<Terminal> = outcomes
+      .iter()
+      .filter(|terminal| terminal.satisfy(pred))
+      .cloned()
+      .collect();
+    // a program that never terminates, spinning or deadlocked, or whose
+    // executions were all cut off, meets no expectation.
+    let verdict = !outcomes.is_empty() && match pred_type {
+      // every terminal state should not satisfy the predicate.
+      PredType::Forbidden => truncated.is_empty() && matching.is_empty(),
+      // every terminal state should satisfy the predicate.
+      PredType::Required => {
+        truncated.is_empty() && matching.len() == outcomes.len()
+      },
+      // at least one terminal state should satisfy the predicate.
+      PredType::Allowed => !matching.is_empty(),
+    };
+
+    CheckResult {
+      name: name.to_string(),
+      outcomes,
+      matching,
+      pred: pred.to_string(),
+      pred_type,
+      states,
+      transitions,
+      truncated,
+      verdict,
+      witness,
+      collision: None,
+    }
+  }
+
+  pub fn is_matching(&self, terminal: &Terminal) -> bool {
+    self.matching.binary_search(terminal).is_ok()
+  }
+
+  // whether every execution was explored to the end.
+  pub fn is_complete(&self) -> bool {
+    self.truncated.is_empty()
+  }
+
+  // whether the test failed only because exploration was cut short, with no
+  // outcome found that decides against it.
+  pub fn is_inconclusive(&self) -> bool {
+    !self.verdict && !self.is_complete() && self.witness.is_none()
+  }
+}
+
+// Outcomes of a program under SC and under a relaxed memory model.
+pub struct Relaxed {
+  pub name: String,
+  // name of the relaxed memory model.
+  pub model: String,
+  // distinct outcomes under each model, in ascending order.
+  pub sc: Vec<Terminal>,
+  pub outcomes: Vec<Terminal>,
+  // outcomes only reachable under the relaxed model, i.e. only because of
+  // store buffering under x86-TSO.
+  pub relaxed: Vec<Terminal>,
+  // bounds that cut either exploration short.
+  pub truncated: Vec<Bound>,
+}
+
+// Sorted distinct terminals.
+fn distinct(terminals: Vec<(Terminal, Trace)>) -> Vec<Terminal> {
+  let mut outcomes: Vec<Terminal> =
+    terminals.into_iter().map(|(terminal, _)| terminal).collect();
+  outcomes.sort();
+  outcomes.dedup();
+  outcomes
+}
+
+impl Relaxed {
+  pub fn new(
+    name: &str,
+    model: &str,
+    sc: Vec<(Terminal, Trace)>,
+    terminals: Vec<(Terminal, Trace)>,
+    mut truncated: Vec<Bound>,
+  ) -> Self {
+    let sc = distinct(sc);
+    let outcomes = distinct(terminals);
+    let relaxed = outcomes
+      .iter()
+      .filter(|terminal| sc.binary_search(terminal).is_err())
+      .cloned()
+      .collect();
+    truncated.sort();
+    truncated.dedup();
+
+    Relaxed {
+      name: name.to_string(),
+      model: model.to_string(),
+      sc,
+      outcomes,
+      relaxed,
+      truncated,
+    }
+  }
+}
+
+// Printed like CheckResult, listing the relaxed outcomes marked with `+>`.
+impl fmt::Display for Relaxed {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    writeln!(f, "Relaxed {}", self.name)?;
+    writeln!(
+      f,
+      "States SC {}, {} {}",
+      self.sc.len(),
+      self.model,
+      self.outcomes.len()
+    )?;
+    for terminal in &self.relaxed {
+      writeln!(f, "+>{}", terminal)?;
+    }
+    if !self.truncated.is_empty() {
+      let bounds: Vec<String> =
+        self.truncated.iter().map(|bound| bound.to_string()).collect();
+      writeln!(f, "Incomplete, bounded by {}", bounds.join(", "))?;
+    }
+    write!(f, "{} relaxed outcomes", self.relaxed.len())
+  }
+}
+
+// Outcomes of a program under the operational and the axiomatic x86-TSO
+// models, which should agree.
+pub struct CrossCheck {
+  pub name: String,
+  // distinct outcomes of each model, in ascending order.
+  pub operational: Vec<Terminal>,
+  pub axiomatic: Vec<Terminal>,
+  // bounds that cut each exploration short.
+  pub operational_truncated: Vec<Bound>,
+  pub axiomatic_truncated: Vec<Bound>,
+}
+
+impl CrossCheck {
+  pub fn new(
+    name: &str,
+    operational: Vec<(Terminal, Trace)>,
+    axiomatic: Vec<Terminal>,
+    operational_truncated: Vec<Bound>,
+    axiomatic_truncated: Vec<Bound>,
+  ) -> Self {
+    CrossCheck {
+      name: name.to_string(),
+      operational: distinct(operational),
+      axiomatic,
+      operational_truncated,
+      axiomatic_truncated,
+    }
+  }
+
+  // outcomes of the operational model missing from the axiomatic one.
+  pub fn only_operational(&self) -> Vec<&Terminal> {
+    self
+      .operational
+      .iter()
+      .filter(|terminal| self.axiomatic.binary_search(terminal).is_err())
+      .collect()
+  }
+
+  // outcomes of the axiomatic model missing from the operational one.
+  pub fn only_axiomatic(&self) -> Vec<&Terminal> {
+    self
+      .axiomatic
+      .iter()
+      .filter(|terminal| self.operational.binary_search(terminal).is_err())
+      .collect()
+  }
+
+  // whether the outcome sets are equal, or, if a model was bounded, whether
+  // its outcomes are included in those of the other.
+  pub fn agrees(&self) -> bool {
+    (!self.operational_truncated.is_empty() ||
+       self.only_axiomatic().is_empty()) &&
+      (!self.axiomatic_truncated.is_empty() ||
+         self.only_operational().is_empty())
+  }
+}
+
+// Lists outcomes found by only one model, marked `o>` for the operational
+// and `a>` for the axiomatic model.
+impl fmt::Display for CrossCheck {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    writeln!(f, "Cross-check {}", self.name)?;
+    writeln!(
+      f,
+      "States operational {}, axiomatic {}",
+      self.operational.len(),
+      self.axiomatic.len()
+    )?;
+    for terminal in self.only_operational() {
+      writeln!(f, "o>{}", terminal)?;
+    }
+    for terminal in self.only_axiomatic() {
+      writeln!(f, "a>{}", terminal)?;
+    }
+    for (model, truncated) in &[
+      ("operational", &self.operational_truncated),
+      ("axiomatic", &self.axiomatic_truncated),
+    ] {
+      if !truncated.is_empty() {
+        let bounds: Vec<String> =
+          truncated.iter().map(|bound| bound.to_string()).collect();
+        writeln!(f, "Incomplete {}, bounded by {}", model, bounds.join(", "))?;
+      }
+    }
+    write!(f, "{}", if self.agrees() { "Agree" } else { "Disagree" })
+  }
+}
+
+// Printed in herd style, outcomes satisfying the predicate are marked with
+// `*>`.
+impl fmt::Display for CheckResult {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    writeln!(f, "Test {} {}", self.name, self.pred_type)?;
+    writeln!(f, "States {}", self.outcomes.len())?;
+    for terminal in &self.outcomes {
+      let mark = if self.is_matching(terminal) { "*>" } else { ":>" };
+      writeln!(f, "{}{}", mark, terminal)?;
+    }
+    let verdict = if self.verdict {
+      "Ok"
+    } else if self.is_inconclusive() {
+      "Inconclusive"
+    } else {
+      "No"
+    };
+    writeln!(f, "{}", verdict)?;
+    if !self.is_complete() {
+      let bounds: Vec<String> =
+        self.truncated.iter().map(|bound| bound.to_string()).collect();
+      writeln!(f, "Incomplete, bounded by {}", bounds.join(", "))?;
+    } else if self.outcomes.is_empty() {
+      writeln!(f, "No terminal state")?;
+    }
+    writeln!(f, "Condition {} {}", self.pred_type, self.pred)?;
+    if !self.verdict {
+      if let Some((ref terminal, ref trace)) = self.witness {
+        writeln!(f, "Witness {}", terminal)?;
+        for step in &trace.0 {
+          writeln!(f, "  {}", step)?;
+        }
+      }
+    }
+    write!(
+      f,
+      "Explored {} states, {} transitions",
+      self.states,
+      self.transitions
+    )?;
+    if let Some(collision) = self.collision {
+      write!(f, "\nFingerprint collision probability {:.1e}", collision)?;
+    }
+    Ok(())
+  }
+}
@@ -0,0 +1,60 @@
+use model::{MemoryModel, Tso};
+use std::fmt;
+
+// Options controlling the exploration of a program.
+#[derive(Clone, Debug)]
+pub struct Config {
+  // memory model giving the transition rules, x86-TSO by default.
+  pub model: &'static dyn MemoryModel,
+  // maximum number of backward jumps taken by each processor.
+  pub max_iterations: Option<usize>,
+  // maximum number of pending writes in each store buffer.
+  pub max_storebuf: Option<usize>,
+  // maximum number of steps of any execution.
+  pub max_steps: Option<usize>,
+  // explore a single transition wherever one independent of all others is
+  // enabled, which preserves the outcomes but not the shortest traces.
+  pub por: bool,
+  // number of threads exploring the state space, sequential if 1.
+  pub threads: usize,
+  // remember visited states by 64-bit fingerprints instead of whole, which
+  // takes far less memory but may miss states on a collision.
+  pub fingerprints: bool,
+  // print the number of states explored after each exploration.
+  pub progress: bool,
+}
+
+impl Default for Config {
+  fn default() -> Self {
+    Config {
+      model: &Tso,
+      max_iterations: None,
+      max_storebuf: None,
+      max_steps: None,
+      por: false,
+      threads: 1,
+      fingerprints: false,
+      progress: false,
+    }
+  }
+}
+
+// A bound of Config that cut exploration short, making the set of outcomes
+// possibly incomplete.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+pub enum Bound {
+  Iterations,
+  StoreBuffer,
+  Steps,
+}
+
+impl fmt::Display for Bound {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let name = match *self {
+      Bound::Iterations => "loop iterations",
+      Bound::StoreBuffer => "store buffer depth",
+      Bound::Steps => "steps",
+    };
+    write!(f, "{}", name)
+  }
+}
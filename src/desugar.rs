@@ -1,85 +1,381 @@
-use ast::{Proc, Operand, Reg};
+use ast::{Proc, Operand, Reg, Value, Label};
 use ast::{Inst, CoreInst};
 use ast::{Prog, CoreProg};
 use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
 use std::vec::Vec;
 
+// Reason for rejecting an instruction, with the processor and index of the
+// offending instruction.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum DesugarError {
+  // destination operand is an immediate value.
+  ImmediateDestination(Proc, usize, Inst),
+  // both operands are memory locations.
+  MemoryToMemory(Proc, usize, Inst),
+  // the internal register is reserved for desugaring.
+  InternalRegister(Proc, usize, Inst),
+  // operands are well-formed but not supported by the instruction.
+  Unsupported(Proc, usize, Inst),
+  // jump to a label not defined on the same processor.
+  UnknownLabel(Proc, usize, Inst),
+  // label defined more than once on the same processor.
+  DuplicateLabel(Proc, usize, Inst),
+}
+
+impl DesugarError {
+  pub fn processor(&self) -> Proc {
+    self.location().0
+  }
+
+  pub fn index(&self) -> usize {
+    self.location().1
+  }
+
+  pub fn inst(&self) -> Inst {
+    self.location().2
+  }
+
+  fn location(&self) -> (Proc, usize, Inst) {
+    match *self {
+      DesugarError::ImmediateDestination(processor, index, inst) |
+      DesugarError::MemoryToMemory(processor, index, inst) |
+      DesugarError::InternalRegister(processor, index, inst) |
+      DesugarError::Unsupported(processor, index, inst) |
+      DesugarError::UnknownLabel(processor, index, inst) |
+      DesugarError::DuplicateLabel(processor, index, inst) => {
+        (processor, index, inst)
+      },
+    }
+  }
+}
+
+impl fmt::Display for DesugarError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let reason = match *self {
+      DesugarError::ImmediateDestination(..) => {
+        "dest operand cannot be immediate value"
+      },
+      DesugarError::MemoryToMemory(..) => {
+        "cannot move between memory locations"
+      },
+      DesugarError::InternalRegister(..) => "cannot use internal register",
+      DesugarError::Unsupported(..) => "unsupported operands",
+      DesugarError::UnknownLabel(..) => "unknown label",
+      DesugarError::DuplicateLabel(..) => "duplicate label",
+    };
+    write!(
+      f,
+      "{}, instruction {} `{}`: {}",
+      self.processor(),
+      self.index(),
+      self.inst(),
+      reason
+    )
+  }
+}
+
+impl Error for DesugarError {}
+
+// Constructor of the error reported for an instruction.
+type Reject = fn(Proc, usize, Inst) -> DesugarError;
+
 fn is_internal_register(operand: Operand) -> bool {
+  matches!(operand, Operand::Reg(Reg::Internal))
+}
+
+fn uses_internal_register(inst: Inst) -> bool {
+  match inst {
+    Inst::Mov(operand1, operand2) |
+    Inst::Xchg(operand1, operand2) |
+    Inst::Alu(_, operand1, operand2) |
+    Inst::Xadd(operand1, operand2) |
+    Inst::Cmpxchg(operand1, operand2) |
+    Inst::LockAlu(_, operand1, operand2) |
+    Inst::LockXadd(operand1, operand2) |
+    Inst::LockCmpxchg(operand1, operand2) |
+    Inst::Cmp(operand1, operand2) |
+    Inst::Test(operand1, operand2) |
+    Inst::Cmovcc(_, operand1, operand2) => {
+      is_internal_register(operand1) || is_internal_register(operand2)
+    },
+    Inst::Unary(_, operand) |
+    Inst::LockUnary(_, operand) |
+    Inst::Setcc(_, operand) => is_internal_register(operand),
+    Inst::Mfence |
+    Inst::Label(_) |
+    Inst::Jmp(_) |
+    Inst::Jcc(..) => false,
+  }
+}
+
+// Desugars a read-modify-write of operand, where compute updates the
+// Internal register holding the value read from memory. Memory operands are
+// accessed by separate read and write steps, so this is not atomic.
+fn desugar_rmw<F>(
+  operand: Operand,
+  desugared: &mut Vec<CoreInst>,
+  compute: F,
+) -> Result<(), Reject>
+where
+  F: Fn(Reg) -> CoreInst,
+{
   match operand {
-    Operand::Reg(Reg::Internal) => true,
-    _ => false,
+    Operand::Imm(_) => return Err(DesugarError::ImmediateDestination),
+    Operand::Reg(reg) => desugared.push(compute(reg)),
+    Operand::MemLoc(memloc) => {
+      desugared.push(CoreInst::Read(Reg::Internal, memloc));
+      desugared.push(compute(Reg::Internal));
+      desugared.push(CoreInst::Write1(memloc, Reg::Internal));
+    },
   }
+  Ok(())
 }
 
-fn desugar_helper(insts: &Vec<Inst>) -> Vec<CoreInst> {
+fn desugar_inst(
+  inst: Inst,
+  desugared: &mut Vec<CoreInst>,
+) -> Result<(), Reject> {
+  match inst {
+    Inst::Mov(operand1, operand2) => {
+      match (operand1, operand2) {
+        (Operand::Imm(_), _) => {
+          return Err(DesugarError::ImmediateDestination);
+        },
+        (Operand::MemLoc(_), Operand::MemLoc(_)) => {
+          return Err(DesugarError::MemoryToMemory);
+        },
+        (Operand::Reg(reg1), Operand::Reg(reg2)) => {
+          desugared.push(CoreInst::Mov1(reg1, reg2));
+        },
+        (Operand::Reg(reg), Operand::Imm(imm)) => {
+          desugared.push(CoreInst::Mov2(reg, imm));
+        },
+        (Operand::Reg(reg), Operand::MemLoc(memloc)) => {
+          desugared.push(CoreInst::Read(reg, memloc));
+        },
+        (Operand::MemLoc(memloc), Operand::Reg(reg)) => {
+          desugared.push(CoreInst::Write1(memloc, reg));
+        },
+        (Operand::MemLoc(memloc), Operand::Imm(imm)) => {
+          desugared.push(CoreInst::Write2(memloc, imm));
+        },
+      }
+    },
+    Inst::Xchg(operand1, operand2) => {
+      match (operand1, operand2) {
+        (Operand::Reg(reg), Operand::MemLoc(memloc)) |
+        (Operand::MemLoc(memloc), Operand::Reg(reg)) => {
+          desugared.push(CoreInst::Lock);
+          desugared.push(CoreInst::Mov1(Reg::Internal, reg));
+          desugared.push(CoreInst::Read(reg, memloc));
+          desugared.push(CoreInst::Write1(memloc, Reg::Internal));
+          desugared.push(CoreInst::Unlock);
+        },
+        (Operand::Imm(_), _) |
+        (_, Operand::Imm(_)) => {
+          return Err(DesugarError::ImmediateDestination);
+        },
+        (Operand::MemLoc(_), Operand::MemLoc(_)) => {
+          return Err(DesugarError::MemoryToMemory);
+        },
+        (_, _) => return Err(DesugarError::Unsupported),
+      }
+    },
+    Inst::Mfence => {
+      desugared.push(CoreInst::Mfence);
+    },
+    Inst::Alu(op, operand1, operand2) => {
+      match (operand1, operand2) {
+        (Operand::MemLoc(_), Operand::MemLoc(_)) => {
+          return Err(DesugarError::MemoryToMemory);
+        },
+        (Operand::Reg(reg), Operand::MemLoc(memloc)) => {
+          desugared.push(CoreInst::Read(Reg::Internal, memloc));
+          desugared.push(CoreInst::Alu1(op, reg, Reg::Internal));
+        },
+        (_, Operand::Reg(reg)) => {
+          desugar_rmw(operand1, desugared, |dest| {
+            CoreInst::Alu1(op, dest, reg)
+          })?;
+        },
+        (_, Operand::Imm(imm)) => {
+          desugar_rmw(operand1, desugared, |dest| {
+            CoreInst::Alu2(op, dest, imm)
+          })?;
+        },
+        (Operand::Imm(_), Operand::MemLoc(_)) => {
+          return Err(DesugarError::ImmediateDestination);
+        },
+      }
+    },
+    Inst::Unary(op, operand) => {
+      desugar_rmw(operand, desugared, |dest| CoreInst::Unary(op, dest))?;
+    },
+    Inst::Xadd(operand1, operand2) |
+    Inst::Cmpxchg(operand1, operand2) => {
+      let reg = match operand2 {
+        Operand::Reg(reg) => reg,
+        Operand::MemLoc(_) if !matches!(operand1, Operand::Reg(_)) => {
+          return Err(DesugarError::MemoryToMemory);
+        },
+        _ => return Err(DesugarError::Unsupported),
+      };
+      if let Inst::Xadd(..) = inst {
+        desugar_rmw(operand1, desugared, |dest| CoreInst::Xadd(dest, reg))?;
+      } else {
+        desugar_rmw(operand1, desugared, |dest| CoreInst::Cmpxchg(dest, reg))?;
+      }
+    },
+    // the lock prefix is only valid with a memory destination, the
+    // instruction runs under the bus lock and drains the store buffer before
+    // unlocking, even if cmpxchg fails.
+    Inst::LockAlu(_, operand, _) |
+    Inst::LockUnary(_, operand) |
+    Inst::LockXadd(operand, _) |
+    Inst::LockCmpxchg(operand, _) => {
+      if !matches!(operand, Operand::MemLoc(_)) {
+        return Err(DesugarError::Unsupported);
+      }
+      let unlocked = match inst {
+        Inst::LockAlu(op, operand1, operand2) => {
+          Inst::Alu(op, operand1, operand2)
+        },
+        Inst::LockUnary(op, operand) => Inst::Unary(op, operand),
+        Inst::LockXadd(operand1, operand2) => Inst::Xadd(operand1, operand2),
+        Inst::LockCmpxchg(operand1, operand2) => {
+          Inst::Cmpxchg(operand1, operand2)
+        },
+        _ => unreachable!(),
+      };
+      desugared.push(CoreInst::Lock);
+      desugar_inst(unlocked, desugared)?;
+      desugared.push(CoreInst::Unlock);
+    },
+    // targets are patched by desugar_helper once all labels are known.
+    Inst::Label(_) => (),
+    Inst::Jmp(_) => desugared.push(CoreInst::Jmp(0)),
+    Inst::Jcc(cond, _) => desugared.push(CoreInst::Jcc(cond, 0)),
+    Inst::Cmp(operand1, operand2) |
+    Inst::Test(operand1, operand2) => {
+      let is_cmp = matches!(inst, Inst::Cmp(..));
+      let compare1 = |reg1: Reg, reg2: Reg| if is_cmp {
+        CoreInst::Cmp1(reg1, reg2)
+      } else {
+        CoreInst::Test1(reg1, reg2)
+      };
+      let compare2 = |reg: Reg, value: Value| if is_cmp {
+        CoreInst::Cmp2(reg, value)
+      } else {
+        CoreInst::Test2(reg, value)
+      };
+      let reg1 = match operand1 {
+        Operand::Imm(_) => return Err(DesugarError::ImmediateDestination),
+        Operand::Reg(reg) => reg,
+        Operand::MemLoc(memloc) => {
+          if let Operand::MemLoc(_) = operand2 {
+            return Err(DesugarError::MemoryToMemory);
+          }
+          desugared.push(CoreInst::Read(Reg::Internal, memloc));
+          Reg::Internal
+        },
+      };
+      match operand2 {
+        Operand::Imm(imm) => desugared.push(compare2(reg1, imm)),
+        Operand::Reg(reg2) => desugared.push(compare1(reg1, reg2)),
+        Operand::MemLoc(memloc) => {
+          desugared.push(CoreInst::Read(Reg::Internal, memloc));
+          desugared.push(compare1(reg1, Reg::Internal));
+        },
+      }
+    },
+    Inst::Setcc(cond, operand) => {
+      match operand {
+        Operand::Imm(_) => return Err(DesugarError::ImmediateDestination),
+        Operand::Reg(reg) => desugared.push(CoreInst::Setcc(cond, reg)),
+        Operand::MemLoc(memloc) => {
+          desugared.push(CoreInst::Setcc(cond, Reg::Internal));
+          desugared.push(CoreInst::Write1(memloc, Reg::Internal));
+        },
+      }
+    },
+    // a memory source is read whether or not the condition holds.
+    Inst::Cmovcc(cond, operand1, operand2) => {
+      match (operand1, operand2) {
+        (Operand::Imm(_), _) => {
+          return Err(DesugarError::ImmediateDestination);
+        },
+        (Operand::Reg(reg1), Operand::Reg(reg2)) => {
+          desugared.push(CoreInst::Cmovcc(cond, reg1, reg2));
+        },
+        (Operand::Reg(reg), Operand::MemLoc(memloc)) => {
+          desugared.push(CoreInst::Read(Reg::Internal, memloc));
+          desugared.push(CoreInst::Cmovcc(cond, reg, Reg::Internal));
+        },
+        (Operand::MemLoc(_), Operand::MemLoc(_)) => {
+          return Err(DesugarError::MemoryToMemory);
+        },
+        (_, _) => return Err(DesugarError::Unsupported),
+      }
+    },
+  }
+  Ok(())
+}
+
+fn desugar_helper(
+  processor: Proc,
+  insts: &[Inst],
+) -> Result<Vec<CoreInst>, DesugarError> {
   let mut desugared = vec![];
+  // core index of each label.
+  let mut labels: HashMap<Label, usize> = HashMap::new();
+  // core index, target label and instruction index of each jump.
+  let mut jumps: Vec<(usize, Label, usize)> = vec![];
 
-  for inst in insts.into_iter() {
+  for (index, inst) in insts.iter().enumerate() {
+    if uses_internal_register(*inst) {
+      return Err(DesugarError::InternalRegister(processor, index, *inst));
+    }
     match *inst {
-      Inst::Mov(operand1, operand2) |
-      Inst::Xchg(operand1, operand2) => {
-        if is_internal_register(operand1) || is_internal_register(operand2) {
-          panic!("cannot use internal register");
-        }
+      Inst::Label(label) if labels.contains_key(&label) => {
+        return Err(DesugarError::DuplicateLabel(processor, index, *inst));
+      },
+      Inst::Label(label) => {
+        labels.insert(label, desugared.len());
       },
-      Inst::Mfence => (),
+      Inst::Jmp(label) |
+      Inst::Jcc(_, label) => jumps.push((desugared.len(), label, index)),
+      _ => (),
     }
+    desugar_inst(*inst, &mut desugared).map_err(
+      |reject| reject(processor, index, *inst),
+    )?;
   }
 
-  for inst in insts.into_iter() {
-    match *inst {
-      Inst::Mov(operand1, operand2) => {
-        match (operand1, operand2) {
-          (Operand::Imm(_), _) => {
-            panic!("dest operand cannot be immediate value");
-          },
-          (Operand::MemLoc(_), Operand::MemLoc(_)) => {
-            panic!("cannot move between memory locations");
-          },
-          (Operand::Reg(reg1), Operand::Reg(reg2)) => {
-            desugared.push(CoreInst::Mov1(reg1, reg2));
-          },
-          (Operand::Reg(reg), Operand::Imm(imm)) => {
-            desugared.push(CoreInst::Mov2(reg, imm));
-          },
-          (Operand::Reg(reg), Operand::MemLoc(memloc)) => {
-            desugared.push(CoreInst::Read(reg, memloc));
-          },
-          (Operand::MemLoc(memloc), Operand::Reg(reg)) => {
-            desugared.push(CoreInst::Write1(memloc, reg));
-          },
-          (Operand::MemLoc(memloc), Operand::Imm(imm)) => {
-            desugared.push(CoreInst::Write2(memloc, imm));
-          },
-        }
-      },
-      Inst::Xchg(operand1, operand2) => {
-        match (operand1, operand2) {
-          (Operand::Reg(reg), Operand::MemLoc(memloc)) |
-          (Operand::MemLoc(memloc), Operand::Reg(reg)) => {
-            desugared.push(CoreInst::Lock);
-            desugared.push(CoreInst::Mov1(Reg::Internal, reg));
-            desugared.push(CoreInst::Read(reg, memloc));
-            desugared.push(CoreInst::Write1(memloc, Reg::Internal));
-            desugared.push(CoreInst::Unlock);
-          },
-          (_, _) => panic!("unimplemented"),
-        }
+  for (at, label, index) in jumps {
+    let target = match labels.get(&label) {
+      Some(target) => *target,
+      None => {
+        return Err(DesugarError::UnknownLabel(processor, index, insts[index]));
       },
-      Inst::Mfence => {
-        desugared.push(CoreInst::Mfence);
-      },
-    }
+    };
+    desugared[at] = match desugared[at] {
+      CoreInst::Jmp(_) => CoreInst::Jmp(target),
+      CoreInst::Jcc(cond, _) => CoreInst::Jcc(cond, target),
+      _ => unreachable!(),
+    };
   }
-  desugared
+  Ok(desugared)
 }
 
 // Desugars Prog into CoreProg, also does some typechecking.
-pub fn desugar(prog: &Prog) -> CoreProg {
+pub fn desugar(prog: &Prog) -> Result<CoreProg, DesugarError> {
   let mut desugared: BTreeMap<Proc, Vec<CoreInst>> = BTreeMap::new();
 
   for (processor, insts) in &prog.0 {
-    desugared.insert(*processor, desugar_helper(insts));
+    desugared.insert(*processor, desugar_helper(*processor, insts)?);
   }
-  CoreProg(desugared)
+  Ok(CoreProg(desugared))
 }
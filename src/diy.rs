@@ -0,0 +1,403 @@
+use ast::{Inst, MemLoc, Operand, Pred, PredType, Proc, Prog, Reg, Value};
+use ast::{MEMLOCS, REGS};
+use graph::Dir;
+use parser::Litmus;
+use state::State;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+// How the accesses of a program-order edge are separated.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug)]
+pub enum Sep {
+  // nothing, e.g. `PodWR`.
+  Plain,
+  // an mfence, e.g. `MFencedWR`.
+  Fence,
+  // the source is a write done by xchg, e.g. `XchgdWR`.
+  Xchg,
+}
+
+// Relation between two consecutive accesses of a cycle, named as by diy.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug)]
+pub enum Edge {
+  // program order within a thread, between accesses to the same location
+  // if same.
+  Po { sep: Sep, same: bool, src: Dir, dst: Dir },
+  // reads-from, from-reads and write serialization, between threads if
+  // external.
+  Rf(bool),
+  Fr(bool),
+  Ws(bool),
+}
+
+impl Edge {
+  pub fn src(&self) -> Dir {
+    match *self {
+      Edge::Po { src, .. } => src,
+      Edge::Rf(_) | Edge::Ws(_) => Dir::Write,
+      Edge::Fr(_) => Dir::Read,
+    }
+  }
+
+  pub fn dst(&self) -> Dir {
+    match *self {
+      Edge::Po { dst, .. } => dst,
+      Edge::Fr(_) | Edge::Ws(_) => Dir::Write,
+      Edge::Rf(_) => Dir::Read,
+    }
+  }
+
+  // whether the edge goes from one thread to another.
+  pub fn is_external(&self) -> bool {
+    match *self {
+      Edge::Po { .. } => false,
+      Edge::Rf(external) | Edge::Fr(external) | Edge::Ws(external) => {
+        external
+      },
+    }
+  }
+
+  pub fn is_same_location(&self) -> bool {
+    match *self {
+      Edge::Po { same, .. } => same,
+      _ => true,
+    }
+  }
+
+  // program-order edges that can join the communication edges of a cycle,
+  // with or without mfence and xchg.
+  pub fn po(fences: bool, xchgs: bool) -> Vec<Edge> {
+    let dirs = [Dir::Write, Dir::Read];
+    let mut edges = vec![];
+    for &src in &dirs {
+      for &dst in &dirs {
+        let mut seps = vec![Sep::Plain];
+        if fences {
+          seps.push(Sep::Fence);
+        }
+        if xchgs && src == Dir::Write {
+          seps.push(Sep::Xchg);
+        }
+        for sep in seps {
+          edges.push(Edge::Po { sep, same: false, src, dst });
+        }
+      }
+    }
+    edges
+  }
+}
+
+fn dir_name(dir: Dir) -> &'static str {
+  match dir {
+    Dir::Read => "R",
+    Dir::Write => "W",
+  }
+}
+
+impl fmt::Display for Edge {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let (name, external) = match *self {
+      Edge::Po { sep, same, src, dst } => {
+        let sep = match sep {
+          Sep::Plain => "Po",
+          Sep::Fence => "MFence",
+          Sep::Xchg => "Xchg",
+        };
+        let loc = if same { "s" } else { "d" };
+        return write!(f, "{}{}{}{}", sep, loc, dir_name(src), dir_name(dst));
+      },
+      Edge::Rf(external) => ("Rf", external),
+      Edge::Fr(external) => ("Fr", external),
+      Edge::Ws(external) => ("Ws", external),
+    };
+    write!(f, "{}{}", name, if external { "e" } else { "i" })
+  }
+}
+
+impl FromStr for Edge {
+  type Err = String;
+
+  fn from_str(name: &str) -> Result<Edge, String> {
+    let unknown = || format!("unknown edge `{}`", name);
+    let com = match name {
+      "Rfe" => Some(Edge::Rf(true)),
+      "Rfi" => Some(Edge::Rf(false)),
+      "Fre" => Some(Edge::Fr(true)),
+      "Fri" => Some(Edge::Fr(false)),
+      "Wse" => Some(Edge::Ws(true)),
+      "Wsi" => Some(Edge::Ws(false)),
+      _ => None,
+    };
+    if let Some(edge) = com {
+      return Ok(edge);
+    }
+
+    let seps =
+      [("Po", Sep::Plain), ("MFence", Sep::Fence), ("Xchg", Sep::Xchg)];
+    let (sep, rest) = seps
+      .iter()
+      .find_map(|&(prefix, sep)| name.strip_prefix(prefix).map(|r| (sep, r)))
+      .ok_or_else(unknown)?;
+    let dir = |c| match c {
+      'W' => Ok(Dir::Write),
+      'R' => Ok(Dir::Read),
+      _ => Err(unknown()),
+    };
+    let chars: Vec<char> = rest.chars().collect();
+    let (same, src, dst) = match chars[..] {
+      [loc, src, dst] if loc == 's' || loc == 'd' => {
+        (loc == 's', dir(src)?, dir(dst)?)
+      },
+      _ => return Err(unknown()),
+    };
+    if sep == Sep::Xchg && src != Dir::Write {
+      return Err(format!("`{}` must start with a write", name));
+    }
+    Ok(Edge::Po { sep, same, src, dst })
+  }
+}
+
+// Parses a cycle of edges separated by spaces, e.g. `PodWR Fre PodWR Fre`.
+pub fn parse_cycle(text: &str) -> Result<Vec<Edge>, String> {
+  text.split_whitespace().map(|name| name.parse()).collect()
+}
+
+fn cycle_name(cycle: &[Edge]) -> String {
+  let names: Vec<String> = cycle.iter().map(|edge| edge.to_string()).collect();
+  names.join("+")
+}
+
+fn find(class: &mut Vec<usize>, k: usize) -> usize {
+  if class[k] != k {
+    let root = find(class, class[k]);
+    class[k] = root;
+  }
+  class[k]
+}
+
+// Builds the test whose condition holds exactly when the accesses of cycle
+// happen as related by its edges: each external edge starts a new thread,
+// every access of a thread is a mov, or an xchg for the source of an Xchg
+// edge, and the values written to a location count up in coherence order.
+pub fn generate(cycle: &[Edge]) -> Result<Litmus, String> {
+  let n = cycle.len();
+  if n == 0 {
+    return Err("empty cycle".to_string());
+  }
+  for k in 0..n {
+    let (edge, next) = (cycle[k], cycle[(k + 1) % n]);
+    if edge.dst() != next.src() {
+      return Err(format!(
+        "`{}` ends with a {} but `{}` starts with a {}",
+        edge,
+        dir_name(edge.dst()),
+        next,
+        dir_name(next.src())
+      ));
+    }
+  }
+  // event k is the source of edge k and the target of edge k - 1, the cycle
+  // starting at the first thread.
+  let start = (0..n)
+    .find(|&k| cycle[(k + n - 1) % n].is_external())
+    .ok_or("the cycle has no external edge")?;
+  let edges: Vec<Edge> = (0..n).map(|k| cycle[(start + k) % n]).collect();
+  let next = |k: usize| (k + 1) % n;
+  let prev = |k: usize| (k + n - 1) % n;
+
+  let mut thread = vec![0; n];
+  for k in 1..n {
+    thread[k] = thread[k - 1] + edges[k - 1].is_external() as usize;
+  }
+  let threads = thread[n - 1] + 1;
+  if threads < 2 {
+    return Err("the cycle has a single thread".to_string());
+  }
+
+  // locations, as classes of events related by same-location edges.
+  let mut class: Vec<usize> = (0..n).collect();
+  for (k, edge) in edges.iter().enumerate() {
+    if edge.is_same_location() {
+      let (a, b) = (find(&mut class, k), find(&mut class, next(k)));
+      class[a] = b;
+    }
+  }
+  let mut roots: Vec<usize> = vec![];
+  let mut memloc = vec![0; n];
+  for (k, loc) in memloc.iter_mut().enumerate() {
+    let root = find(&mut class, k);
+    *loc = match roots.iter().position(|&r| r == root) {
+      Some(i) => i,
+      None => {
+        roots.push(root);
+        roots.len() - 1
+      },
+    };
+  }
+  for (k, edge) in edges.iter().enumerate() {
+    if !edge.is_same_location() && memloc[k] == memloc[next(k)] {
+      return Err(format!("`{}` relates accesses to the same location", edge));
+    }
+  }
+  if roots.len() > MEMLOCS.len() {
+    return Err("the cycle accesses too many locations".to_string());
+  }
+
+  // the write a read reads from and the write it is before in coherence, if
+  // the cycle says.
+  let is_read = |k: usize| edges[k].src() == Dir::Read;
+  let source = |k: usize| {
+    let edge = edges[prev(k)];
+    if is_read(k) && edge.is_same_location() && edge.src() == Dir::Write {
+      Some(prev(k))
+    } else {
+      None
+    }
+  };
+  let before = |k: usize| {
+    let edge = edges[k];
+    if is_read(k) && edge.is_same_location() && edge.dst() == Dir::Write {
+      Some(next(k))
+    } else {
+      None
+    }
+  };
+
+  // coherence of each location, ordering the writes as the cycle says and
+  // otherwise as they appear in it.
+  let mut co: Vec<(usize, usize)> = vec![];
+  for (k, edge) in edges.iter().enumerate() {
+    if !is_read(k) && edge.is_same_location() && edge.dst() == Dir::Write {
+      co.push((k, next(k)));
+    }
+    if let (Some(w1), Some(w2)) = (source(k), before(k)) {
+      co.push((w1, w2));
+    }
+  }
+  let mut value = vec![0; n];
+  for loc in 0..roots.len() {
+    let mut writes: Vec<usize> =
+      (0..n).filter(|&k| memloc[k] == loc && !is_read(k)).collect();
+    let mut count = 0;
+    while !writes.is_empty() {
+      let first = writes
+        .iter()
+        .position(|&w| !co.iter().any(|&(a, b)| b == w && writes.contains(&a)))
+        .ok_or("the cycle orders writes in a coherence cycle")?;
+      count += 1;
+      value[writes.remove(first)] = count;
+    }
+  }
+
+  let mut prog: BTreeMap<Proc, Vec<Inst>> = BTreeMap::new();
+  let mut xchgs: Vec<(Proc, Reg, Value)> = vec![];
+  let mut preds: Vec<Pred> = vec![];
+  let mut regs = vec![0; threads];
+  for k in 0..n {
+    let processor = Proc(thread[k] as u32);
+    let insts = prog.entry(processor).or_default();
+    if let Edge::Po { sep: Sep::Fence, .. } = edges[prev(k)] {
+      insts.push(Inst::Mfence);
+    }
+    let location = Operand::MemLoc(MemLoc(MEMLOCS[memloc[k]]));
+    let locked = matches!(edges[k], Edge::Po { sep: Sep::Xchg, .. });
+    if !is_read(k) && !locked {
+      insts.push(Inst::Mov(location, Operand::Imm(Value(value[k]))));
+      continue;
+    }
+    let reg = *REGS.get(regs[thread[k]]).ok_or_else(|| {
+      format!("{} needs more than {} registers", processor, REGS.len())
+    })?;
+    regs[thread[k]] += 1;
+    if locked {
+      insts.push(Inst::Xchg(location, Operand::Reg(reg)));
+      xchgs.push((processor, reg, Value(value[k])));
+      continue;
+    }
+    insts.push(Inst::Mov(Operand::Reg(reg), location));
+    let read = match (source(k), before(k)) {
+      (Some(w), _) => Some(value[w]),
+      (None, Some(w)) => Some(value[w] - 1),
+      (None, None) => None,
+    };
+    if let Some(read) = read {
+      preds.push(Pred::Reg(processor, reg, Value(read)));
+    }
+  }
+  // the last write in coherence, where the reads do not tell.
+  for (loc, name) in MEMLOCS.iter().enumerate().take(roots.len()) {
+    let writes = (0..n).filter(|&k| memloc[k] == loc && !is_read(k)).count();
+    if writes > 1 {
+      preds.push(Pred::MemLoc(MemLoc(name), Value(writes as i32)));
+    }
+  }
+
+  let prog = Prog(prog);
+  let mut init = State::builder(&prog);
+  for name in MEMLOCS.iter().take(roots.len()) {
+    init = init.mem(MemLoc(name), Value(0));
+  }
+  for (processor, reg, value) in xchgs {
+    init = init.reg(processor, reg, value);
+  }
+  let pred = if preds.len() == 1 {
+    preds.pop().unwrap()
+  } else {
+    Pred::And(preds)
+  };
+
+  Ok(Litmus {
+    name: cycle_name(cycle),
+    prog,
+    init: init.build(),
+    pred,
+    pred_type: PredType::Allowed,
+  })
+}
+
+// Cycles of threads threads, each running one edge of po and linked to the
+// next by an external communication edge, that make a test. Each cycle is
+// given once, in its least rotation.
+pub fn cycles(threads: usize, po: &[Edge]) -> Vec<Vec<Edge>> {
+  let coms = [Edge::Rf(true), Edge::Fr(true), Edge::Ws(true)];
+  let mut cycles: Vec<Vec<Edge>> = vec![vec![]];
+
+  for _ in 0..threads {
+    let mut longer = vec![];
+    for cycle in &cycles {
+      for &edge in po {
+        if cycle.last().map_or(false, |com: &Edge| com.dst() != edge.src()) {
+          continue;
+        }
+        for &com in &coms {
+          if com.src() == edge.dst() {
+            let mut cycle = cycle.clone();
+            cycle.push(edge);
+            cycle.push(com);
+            longer.push(cycle);
+          }
+        }
+      }
+    }
+    cycles = longer;
+  }
+
+  let mut canonical: Vec<Vec<Edge>> = cycles
+    .into_iter()
+    .filter(|cycle| generate(cycle).is_ok())
+    .map(|cycle| {
+      (0..threads)
+        .map(|t| {
+          let mut rotated = cycle.clone();
+          rotated.rotate_left(2 * t);
+          rotated
+        })
+        .min()
+        .unwrap()
+    })
+    .collect();
+  canonical.sort();
+  canonical.dedup();
+  canonical
+}
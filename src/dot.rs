@@ -0,0 +1,74 @@
+use state::State;
+use std::fmt::Write;
+use trace::Step;
+
+// Explored state graph, states are indexed by id, the initial state first.
+#[derive(Clone, Default, Debug)]
+pub struct StateGraph {
+  pub states: Vec<State>,
+  // transitions between state ids, including those to visited states.
+  pub edges: Vec<(usize, usize, Step)>,
+}
+
+// Label of a state, one line per processor with its ip, registers and model
+// state, such as the store buffer, then memory and the lock owner, if any.
+fn label(state: &State) -> String {
+  let mut lines = vec![];
+
+  for (processor, proc_state) in &state.procs {
+    let mut line = format!("{}", processor);
+    match proc_state.ip {
+      Some(ip) => write!(line, " ip={}", ip).unwrap(),
+      None => line.push_str(" done"),
+    }
+    for (reg, value) in &proc_state.regs {
+      write!(line, " {}={}", reg, value).unwrap();
+    }
+    write!(line, " [{}]", proc_state.model).unwrap();
+    lines.push(line);
+  }
+  let mem: Vec<String> = state
+    .mem
+    .iter()
+    .map(|(memloc, value)| format!("{}={}", memloc, value))
+    .collect();
+  lines.push(mem.join(" "));
+  if let Some(owner) = state.lock_owner {
+    lines.push(format!("locked by {}", owner));
+  }
+  lines.join("\\n")
+}
+
+impl StateGraph {
+  // Graphviz rendering, the initial state is bold and final states have a
+  // double border.
+  pub fn to_dot(&self, name: &str) -> String {
+    let mut dot = String::new();
+
+    writeln!(dot, "digraph \"{}\" {{", name).unwrap();
+    writeln!(dot, "  node [shape=box, fontname=monospace];").unwrap();
+    for (id, state) in self.states.iter().enumerate() {
+      let style = if id == 0 {
+        ", style=bold"
+      } else if state.is_final() {
+        ", peripheries=2"
+      } else {
+        ""
+      };
+      writeln!(dot, "  s{} [label=\"{}\"{}];", id, label(state), style)
+        .unwrap();
+    }
+    for &(from, to, step) in &self.edges {
+      writeln!(
+        dot,
+        "  s{} -> s{} [label=\"{} {}\"];",
+        from,
+        to,
+        step.processor,
+        step.rule
+      ).unwrap();
+    }
+    dot.push_str("}\n");
+    dot
+  }
+}
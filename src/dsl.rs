@@ -0,0 +1,103 @@
+use parser::{self, Litmus, ParseError};
+
+// Litmus test written inline, with the processors separated by `|` and
+// their instructions by `;`, as in
+//
+//   let test = litmus! {
+//     SB { x = 0; y = 0; }
+//     P0: mov [x], $1; mov eax, [y]
+//     | P1: mov [y], $1; mov ebx, [x]
+//     exists (0:eax = 0 && 1:ebx = 0)
+//   };
+//
+// The init block may be left out, and since `/\` and `\/` are not Rust
+// tokens, conditions are written with `&&` and `||`. Panics if the test is
+// malformed.
+#[macro_export]
+macro_rules! litmus {
+  ($($tokens:tt)*) => {
+    match $crate::dsl::parse(stringify!($($tokens)*)) {
+      Ok(test) => test,
+      Err(e) => panic!("invalid litmus! test: {}", e.message),
+    }
+  };
+}
+
+// Parses a test written as for litmus!, by rewriting it in the herd format.
+pub fn parse(text: &str) -> Result<Litmus, ParseError> {
+  let text = to_herd(text).map_err(|message| ParseError { line: 1, message });
+  parser::parse(&text?)
+}
+
+// Position of the final condition, the first `exists` or `forall` word and
+// a `~` before it.
+fn condition_start(text: &str) -> Option<usize> {
+  let is_word = |c: char| c.is_ascii_alphanumeric() || c == '_';
+  let start = (0..text.len()).find(|&i| {
+    text.is_char_boundary(i) &&
+      (text[i..].starts_with("exists") || text[i..].starts_with("forall")) &&
+      !text[..i].ends_with(is_word) &&
+      !text[i + 6..].starts_with(is_word)
+  })?;
+  let tilde = text[..start].trim_end();
+  Some(tilde.strip_suffix('~').map_or(start, |tilde| tilde.len()))
+}
+
+fn to_herd(text: &str) -> Result<String, String> {
+  // stringify! may break lines anywhere between tokens.
+  let text = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+
+  let (name, rest) = match text.strip_prefix('"') {
+    Some(quoted) => {
+      let end = quoted.find('"').ok_or("unterminated test name")?;
+      (&quoted[..end], &quoted[end + 1..])
+    },
+    None => {
+      let end = text.find([' ', '{']).unwrap_or(text.len());
+      (&text[..end], &text[end..])
+    },
+  };
+  if name.is_empty() {
+    return Err("missing test name".to_string());
+  }
+  // older compilers stringify `-1` as `- 1`.
+  let rest = rest.trim_start().replace("- ", "-");
+  let rest = rest.as_str();
+  let (init, rest) = match rest.strip_prefix('{') {
+    Some(rest) => {
+      let end = rest.find('}').ok_or("unterminated init block")?;
+      (&rest[..end], &rest[end + 1..])
+    },
+    None => ("", rest),
+  };
+  let start = condition_start(rest).ok_or("missing final condition")?;
+  let (prog, condition) = rest.split_at(start);
+
+  let mut processors = vec![];
+  let mut columns: Vec<Vec<&str>> = vec![];
+  for column in prog.split('|') {
+    let (processor, insts) =
+      column.split_once(':').ok_or("expected `P0:` before instructions")?;
+    processors.push(processor.trim());
+    columns.push(
+      insts
+        .split(';')
+        .map(|inst| inst.trim())
+        .filter(|inst| !inst.is_empty())
+        .collect(),
+    );
+  }
+
+  let mut herd = format!("X86 {}\n{{ {} }}\n", name, init);
+  herd.push_str(&format!("{} ;\n", processors.join(" | ")));
+  let rows = columns.iter().map(|insts| insts.len()).max().unwrap_or(0);
+  for row in 0..rows {
+    let cells: Vec<&str> = columns
+      .iter()
+      .map(|insts| insts.get(row).cloned().unwrap_or(""))
+      .collect();
+    herd.push_str(&format!("{} ;\n", cells.join(" | ")));
+  }
+  herd.push_str(&condition.replace("&&", "/\\").replace("||", "\\/"));
+  Ok(herd)
+}
@@ -0,0 +1,23 @@
+use state::State;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+// 64-bit fingerprint of state. Distinct states share a fingerprint with
+// probability about 2^-64, in which case exploring by fingerprints wrongly
+// treats the second as visited.
+pub fn fingerprint(state: &State) -> u64 {
+  let mut hasher = DefaultHasher::new();
+  state.hash(&mut hasher);
+  hasher.finish()
+}
+
+// Estimated probability that exploring by fingerprints missed a state, as
+// TLC computes it: each of the transitions to a visited state may have
+// collided with any of the other states, giving states * (transitions + 1 -
+// states) chances of 2^-64, as the initial state is visited without a
+// transition. Optimistic in that it assumes fingerprints are uniformly
+// distributed.
+pub fn collision_probability(states: usize, transitions: usize) -> f64 {
+  let revisits = (transitions + 1).saturating_sub(states);
+  (states as f64 * revisits as f64 / 2f64.powi(64)).min(1.0)
+}
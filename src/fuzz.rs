@@ -0,0 +1,421 @@
+use ast::{AluOp, Inst, MemLoc, Operand, Pred, PredType, Proc, Prog};
+use ast::{UnaryOp, Value, MEMLOCS, REGS};
+use axiomatic::axiomatic;
+use config::Config;
+use desugar::desugar;
+use model::Sc;
+use parser::Litmus;
+use run::run;
+use state::{State, Terminal};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+// Bounds on the size of random programs.
+#[derive(Clone, Copy, Debug)]
+pub struct Limits {
+  // maximum number of processors, at least 2 are generated if allowed.
+  pub processors: usize,
+  // maximum number of instructions of each processor.
+  pub insts: usize,
+  // number of memory locations the instructions draw from, at most 8.
+  pub memlocs: usize,
+}
+
+impl Default for Limits {
+  fn default() -> Self {
+    Limits { processors: 3, insts: 3, memlocs: 2 }
+  }
+}
+
+// Deterministic xorshift64* generator, so that a seed reproduces a program.
+#[derive(Clone, Debug)]
+pub struct Rng(u64);
+
+impl Rng {
+  pub fn new(seed: u64) -> Self {
+    // splitmix64 of the seed, which is never 0 for small seeds.
+    let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    Rng((z ^ (z >> 31)) | 1)
+  }
+
+  pub fn next_u64(&mut self) -> u64 {
+    self.0 ^= self.0 >> 12;
+    self.0 ^= self.0 << 25;
+    self.0 ^= self.0 >> 27;
+    self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+  }
+
+  // uniform in 0..n, n must be positive.
+  pub fn below(&mut self, n: usize) -> usize {
+    (self.next_u64() % n as u64) as usize
+  }
+
+  // uniform in low..=high.
+  pub fn range(&mut self, low: usize, high: usize) -> usize {
+    low + self.below(high - low + 1)
+  }
+}
+
+// Random instruction at index of its processor, with the register of the
+// index so that reads do not overwrite each other.
+fn random_inst(rng: &mut Rng, memlocs: &[MemLoc], index: usize) -> Inst {
+  let memloc = Operand::MemLoc(memlocs[rng.below(memlocs.len())]);
+  let reg = Operand::Reg(REGS[index % REGS.len()]);
+  let imm = Operand::Imm(Value(rng.range(1, 2) as i32));
+  // plain accesses dominate, as they are where the models differ and any
+  // fence or locked instruction between them hides the difference.
+  match rng.below(16) {
+    0..=5 => Inst::Mov(memloc, imm),
+    6..=11 => Inst::Mov(reg, memloc),
+    12 => Inst::Mov(memloc, reg),
+    13 => Inst::Mfence,
+    14 => Inst::Xchg(memloc, reg),
+    _ => match rng.below(3) {
+      0 => Inst::LockAlu(AluOp::Add, memloc, imm),
+      1 => Inst::LockUnary(UnaryOp::Inc, memloc),
+      _ => Inst::LockXadd(memloc, reg),
+    },
+  }
+}
+
+// Random straight-line program within limits.
+pub fn random_prog(rng: &mut Rng, limits: &Limits) -> Prog {
+  let memlocs: Vec<MemLoc> = MEMLOCS
+    .iter()
+    .take(limits.memlocs.clamp(1, MEMLOCS.len()))
+    .map(|name| MemLoc::intern(name))
+    .collect();
+  let max = limits.processors.max(1);
+  let processors = rng.range(max.min(2), max);
+  let mut prog = BTreeMap::new();
+  for processor in 0..processors {
+    let len = rng.range(1, limits.insts.max(1));
+    let insts = (0..len).map(|i| random_inst(rng, &memlocs, i)).collect();
+    prog.insert(Proc(processor as u32), insts);
+  }
+  Prog(prog)
+}
+
+// Property that a discrepancy violates.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Check {
+  // every outcome under SC is an outcome under the model.
+  Sc,
+  // the model has the outcomes of the axiomatic x86-TSO model.
+  Axiomatic,
+  // partial-order reduction, several threads and fingerprints each keep
+  // the outcomes of a plain exploration.
+  Por,
+  Threads,
+  Fingerprints,
+}
+
+impl fmt::Display for Check {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let name = match *self {
+      Check::Sc => "SC outcomes",
+      Check::Axiomatic => "axiomatic x86-TSO",
+      Check::Por => "partial-order reduction",
+      Check::Threads => "parallel exploration",
+      Check::Fingerprints => "fingerprints",
+    };
+    write!(f, "{}", name)
+  }
+}
+
+impl Check {
+  // option of the command line with which a test of the discrepancy fails,
+  // None if it fails under the model alone.
+  pub fn flag(&self) -> Option<&'static str> {
+    match *self {
+      Check::Sc | Check::Axiomatic => None,
+      Check::Por => Some("--por"),
+      Check::Threads => Some("--threads 2"),
+      Check::Fingerprints => Some("--fingerprints"),
+    }
+  }
+
+  // suffix of the names of tests of the discrepancy, after their seed.
+  fn suffix(&self) -> &'static str {
+    match *self {
+      Check::Sc | Check::Axiomatic => "",
+      Check::Por => "-por",
+      Check::Threads => "-threads",
+      Check::Fingerprints => "-fingerprints",
+    }
+  }
+}
+
+// An outcome on which the model disagrees with a reference: SC for
+// Check::Sc, the axiomatic model for Check::Axiomatic and a plain
+// exploration otherwise.
+#[derive(Clone, Debug)]
+pub struct Discrepancy {
+  pub check: Check,
+  pub outcome: Terminal,
+  // whether the reference has the outcome, which the model then misses.
+  pub expected: bool,
+}
+
+impl fmt::Display for Discrepancy {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let option = format!("with {}", self.check.flag().unwrap_or_default());
+    let (reference, model) = match self.check {
+      Check::Sc => ("under SC", "under the model"),
+      Check::Axiomatic => ("axiomatically", "operationally"),
+      _ => ("by a plain exploration", option.as_str()),
+    };
+    let (reached, missed) = if self.expected {
+      (reference, model)
+    } else {
+      (model, reference)
+    };
+    write!(
+      f,
+      "{}: {} reached {} but not {}",
+      self.check,
+      self.outcome,
+      reached,
+      missed
+    )
+  }
+}
+
+impl Discrepancy {
+  // condition that holds of the discrepancy's outcome alone.
+  pub fn pred(&self) -> Pred {
+    let mut preds = vec![];
+    for (processor, proc_terminal) in &self.outcome.procs {
+      for (reg, value) in &proc_terminal.regs {
+        preds.push(Pred::Reg(*processor, *reg, *value));
+      }
+    }
+    for (memloc, value) in &self.outcome.mem {
+      preds.push(Pred::MemLoc(*memloc, *value));
+    }
+    Pred::And(preds)
+  }
+
+  // test that fails exactly if the discrepancy shows, asking for the
+  // outcome if the reference has it and forbidding it otherwise.
+  pub fn test(&self, name: &str, prog: &Prog) -> Litmus {
+    let pred_type = if self.expected {
+      PredType::Allowed
+    } else {
+      PredType::Forbidden
+    };
+    Litmus {
+      name: name.to_string(),
+      prog: prog.clone(),
+      init: init(prog),
+      pred: self.pred(),
+      pred_type,
+    }
+  }
+}
+
+// Initial state of a random program, every location it accesses 0.
+pub fn init(prog: &Prog) -> State {
+  let mut builder = State::builder(prog);
+  let memlocs: BTreeSet<MemLoc> = prog
+    .0
+    .values()
+    .flatten()
+    .flat_map(|inst| operands(inst).into_iter())
+    .filter_map(|operand| match operand {
+      Operand::MemLoc(memloc) => Some(memloc),
+      _ => None,
+    })
+    .collect();
+  for memloc in memlocs {
+    builder = builder.mem(memloc, Value(0));
+  }
+  builder.build()
+}
+
+fn operands(inst: &Inst) -> Vec<Operand> {
+  match *inst {
+    Inst::Mov(a, b) |
+    Inst::Xchg(a, b) |
+    Inst::Alu(_, a, b) |
+    Inst::Xadd(a, b) |
+    Inst::Cmpxchg(a, b) |
+    Inst::LockAlu(_, a, b) |
+    Inst::LockXadd(a, b) |
+    Inst::LockCmpxchg(a, b) |
+    Inst::Cmp(a, b) |
+    Inst::Test(a, b) |
+    Inst::Cmovcc(_, a, b) => vec![a, b],
+    Inst::Unary(_, a) | Inst::LockUnary(_, a) | Inst::Setcc(_, a) => vec![a],
+    Inst::Mfence | Inst::Label(_) | Inst::Jmp(_) | Inst::Jcc(..) => vec![],
+  }
+}
+
+// First outcome of reference missing from other, or with both, the first
+// outcome of other missing from reference.
+fn differ(
+  check: Check,
+  reference: &BTreeSet<Terminal>,
+  other: &BTreeSet<Terminal>,
+  both: bool,
+) -> Option<Discrepancy> {
+  if let Some(outcome) = reference.difference(other).next() {
+    let outcome = outcome.clone();
+    return Some(Discrepancy { check, outcome, expected: true });
+  }
+  if !both {
+    return None;
+  }
+  other.difference(reference).next().map(|outcome| Discrepancy {
+    check,
+    outcome: outcome.clone(),
+    expected: false,
+  })
+}
+
+// Explores prog from its initial state under the model of config, which
+// should be x86-TSO, and checks it against SC, the axiomatic model and the
+// other exploration options, returning the first discrepancy. Checks whose
+// explorations were truncated by the bounds of config are skipped.
+pub fn compare(prog: &Prog, config: &Config) -> Option<Discrepancy> {
+  let desugared = desugar(prog).ok()?;
+  let init = init(prog);
+  let explore = |config: &Config| {
+    let exploration = run("fuzz", desugared.clone(), init.clone(), config);
+    let outcomes: BTreeSet<Terminal> = exploration
+      .terminals
+      .into_iter()
+      .map(|(terminal, _)| terminal)
+      .collect();
+    if exploration.truncated.is_empty() {
+      Some(outcomes)
+    } else {
+      None
+    }
+  };
+
+  let plain = Config {
+    por: false,
+    threads: 1,
+    fingerprints: false,
+    progress: false,
+    ..config.clone()
+  };
+  let outcomes = explore(&plain)?;
+
+  let sc = explore(&Config { model: &Sc, ..plain.clone() });
+  let found = sc.and_then(|sc| differ(Check::Sc, &sc, &outcomes, false));
+  if found.is_some() {
+    return found;
+  }
+
+  let reference = axiomatic(&desugared, &init, &plain);
+  if reference.truncated.is_empty() {
+    let reference: BTreeSet<Terminal> =
+      reference.outcomes.into_iter().collect();
+    let found = differ(Check::Axiomatic, &reference, &outcomes, true);
+    if found.is_some() {
+      return found;
+    }
+  }
+
+  let variants = [
+    (Check::Por, Config { por: true, ..plain.clone() }),
+    (Check::Threads, Config { threads: 2, ..plain.clone() }),
+    (Check::Fingerprints, Config { fingerprints: true, ..plain.clone() }),
+  ];
+  variants.iter().find_map(|(check, config)| {
+    explore(config)
+      .and_then(|other| differ(*check, &outcomes, &other, true))
+  })
+}
+
+// Copy of prog without the instruction at index of processor, dropping the
+// processor if it is left empty and renumbering the rest from 0.
+fn remove(prog: &Prog, processor: Proc, index: usize) -> Prog {
+  let mut insts: Vec<Vec<Inst>> = vec![];
+  for (p, column) in &prog.0 {
+    let mut column = column.clone();
+    if *p == processor {
+      column.remove(index);
+    }
+    if !column.is_empty() {
+      insts.push(column);
+    }
+  }
+  Prog(
+    insts
+      .into_iter()
+      .enumerate()
+      .map(|(p, column)| (Proc(p as u32), column))
+      .collect(),
+  )
+}
+
+// Shrinks prog by removing instructions one at a time for as long as the
+// same check keeps failing, returning the smallest program found and its
+// discrepancy.
+pub fn minimize(
+  prog: &Prog,
+  discrepancy: Discrepancy,
+  config: &Config,
+) -> (Prog, Discrepancy) {
+  let mut prog = prog.clone();
+  let mut discrepancy = discrepancy;
+  'shrink: loop {
+    let positions: Vec<(Proc, usize)> = prog
+      .0
+      .iter()
+      .flat_map(|(p, insts)| (0..insts.len()).map(move |i| (*p, i)))
+      .collect();
+    for (processor, index) in positions {
+      let smaller = remove(&prog, processor, index);
+      if smaller.0.is_empty() {
+        continue;
+      }
+      match compare(&smaller, config) {
+        Some(found) if found.check == discrepancy.check => {
+          prog = smaller;
+          discrepancy = found;
+          continue 'shrink;
+        },
+        _ => (),
+      }
+    }
+    return (prog, discrepancy);
+  }
+}
+
+// A minimized discrepancy found by fuzz, with a test reproducing it.
+pub struct Counterexample {
+  // seed of the random program it was minimized from.
+  pub seed: u64,
+  pub discrepancy: Discrepancy,
+  pub test: Litmus,
+}
+
+// Checks count random programs within limits, the i-th from seed + i,
+// returning a minimized counterexample for each that shows a discrepancy.
+// Tests are named after the seed and the option they fail with, if any,
+// e.g. `fuzz-34` or `fuzz-34-por`.
+pub fn fuzz(
+  seed: u64,
+  count: usize,
+  limits: &Limits,
+  config: &Config,
+) -> Vec<Counterexample> {
+  let mut counterexamples = vec![];
+  for i in 0..count as u64 {
+    let seed = seed.wrapping_add(i);
+    let prog = random_prog(&mut Rng::new(seed), limits);
+    if let Some(discrepancy) = compare(&prog, config) {
+      let (prog, discrepancy) = minimize(&prog, discrepancy, config);
+      let name = format!("fuzz-{}{}", seed, discrepancy.check.suffix());
+      let test = discrepancy.test(&name, &prog);
+      counterexamples.push(Counterexample { seed, discrepancy, test });
+    }
+  }
+  counterexamples
+}
@@ -0,0 +1,241 @@
+use ast::{CoreProg, CoreInst, Proc, MemLoc, Value};
+use model::MemoryModel;
+use run::prepare;
+use state::State;
+use std::collections::BTreeMap;
+use std::collections::VecDeque;
+use std::fmt;
+use trace::{Rule, Trace};
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug)]
+pub enum Dir {
+  Read,
+  Write,
+}
+
+// Memory event of an execution.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Event {
+  // None for the write of the initial value of a location.
+  pub processor: Option<Proc>,
+  pub dir: Dir,
+  pub memloc: MemLoc,
+  pub value: Value,
+  // whether the event belongs to a locked instruction.
+  pub locked: bool,
+}
+
+// Execution graph, relations are given as pairs of indices into events.
+// Program order and coherence are total orders per processor and per
+// location, given by their immediate successors, and from-reads relates a
+// read to the write following the one it reads from in coherence.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Graph {
+  pub events: Vec<Event>,
+  pub po: Vec<(usize, usize)>,
+  pub rf: Vec<(usize, usize)>,
+  pub co: Vec<(usize, usize)>,
+  pub fr: Vec<(usize, usize)>,
+}
+
+impl Graph {
+  // Rebuilds the execution of prog taking trace from init under model. The
+  // state space does not record which write a value came from, so the
+  // graph follows the writes through store buffers and memory while
+  // replaying the trace.
+  pub fn replay(
+    prog: &CoreProg,
+    init: &State,
+    trace: &Trace,
+    model: &dyn MemoryModel,
+  ) -> Self {
+    let mut events: Vec<Event> = vec![];
+    // last event of each processor, coherence-last write of each location
+    // and pending writes of each processor.
+    let mut last: BTreeMap<Proc, usize> = BTreeMap::new();
+    let mut latest: BTreeMap<MemLoc, usize> = BTreeMap::new();
+    let mut storebufs: BTreeMap<Proc, VecDeque<usize>> = BTreeMap::new();
+    let mut graph = Graph {
+      events: vec![],
+      po: vec![],
+      rf: vec![],
+      co: vec![],
+      fr: vec![],
+    };
+
+    for (&memloc, &value) in &init.mem {
+      latest.insert(memloc, events.len());
+      events.push(Event {
+        processor: None,
+        dir: Dir::Write,
+        memloc,
+        value,
+        locked: false,
+      });
+    }
+
+    let mut state = init.clone();
+    prepare(prog, model, &mut state);
+    for step in &trace.0 {
+      let processor = step.processor;
+      let transition = model
+        .rules()
+        .iter()
+        .find(|&&(rule, _)| rule == step.rule)
+        .unwrap()
+        .1;
+      let nstate = transition(processor, prog, &state).unwrap();
+      let locked = state.lock_owner == Some(processor);
+      let storebuf = storebufs.entry(processor).or_default();
+
+      // event of the step, if it accesses memory.
+      let event = match (step.rule, step.inst) {
+        (Rule::Read, Some(CoreInst::Read(reg, memloc))) => {
+          let buffered = storebuf
+            .iter()
+            .rev()
+            .find(|&&w| events[w].memloc == memloc)
+            .cloned();
+          let source = match buffered.or_else(|| latest.get(&memloc).cloned()) {
+            Some(source) => source,
+            None => {
+              // location never initialized, the read sees an implicit 0.
+              latest.insert(memloc, events.len());
+              events.push(Event {
+                processor: None,
+                dir: Dir::Write,
+                memloc,
+                value: Value::default(),
+                locked: false,
+              });
+              events.len() - 1
+            },
+          };
+          graph.rf.push((source, events.len()));
+          let value = nstate.procs[&processor].get(reg);
+          Some(Event {
+            processor: Some(processor),
+            dir: Dir::Read,
+            memloc,
+            value,
+            locked,
+          })
+        },
+        (Rule::Write, Some(inst)) => {
+          let (memloc, value) = match inst {
+            CoreInst::Write1(memloc, reg) => {
+              (memloc, state.procs[&processor].get(reg))
+            },
+            CoreInst::Write2(memloc, value) => (memloc, value),
+            _ => unreachable!(),
+          };
+          Some(Event {
+            processor: Some(processor),
+            dir: Dir::Write,
+            memloc,
+            value,
+            locked,
+          })
+        },
+        _ => None,
+      };
+      if let Some(event) = event {
+        let id = events.len();
+        if let Some(&prev) = last.get(&processor) {
+          graph.po.push((prev, id));
+        }
+        last.insert(processor, id);
+        events.push(event);
+        if event.dir == Dir::Write {
+          storebuf.push_back(id);
+          // buffered unless the model wrote it straight to memory.
+          if nstate.procs[&processor].model.pending() <=
+            state.procs[&processor].model.pending()
+          {
+            flush(&mut graph, &mut latest, storebuf, &events);
+          }
+        }
+      }
+      if step.rule == Rule::Tau {
+        flush(&mut graph, &mut latest, storebuf, &events);
+      }
+      state = nstate;
+    }
+
+    // from-reads: each read precedes the coherence successor of its source.
+    for &(w, r) in &graph.rf {
+      if let Some(&(_, next)) = graph.co.iter().find(|&&(w1, _)| w1 == w) {
+        graph.fr.push((r, next));
+      }
+    }
+    graph.events = events;
+    graph
+  }
+}
+
+// Moves the oldest pending write of storebuf to memory.
+fn flush(
+  graph: &mut Graph,
+  latest: &mut BTreeMap<MemLoc, usize>,
+  storebuf: &mut VecDeque<usize>,
+  events: &[Event],
+) {
+  let id = storebuf.pop_front().unwrap();
+  if let Some(prev) = latest.insert(events[id].memloc, id) {
+    graph.co.push((prev, id));
+  }
+}
+
+// Name of the event with index id, a to z and then e26, e27...
+fn name(id: usize) -> String {
+  if id < 26 {
+    ((b'a' + id as u8) as char).to_string()
+  } else {
+    format!("e{}", id)
+  }
+}
+
+// Printed as a list of events, e.g. `c: P0 W x=1`, with init writes on
+// processor `i` and locked events marked with `*`, followed by the
+// relations.
+impl fmt::Display for Graph {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    for (id, event) in self.events.iter().enumerate() {
+      let processor = match event.processor {
+        Some(processor) => processor.to_string(),
+        None => "i".to_string(),
+      };
+      let dir = match event.dir {
+        Dir::Read => "R",
+        Dir::Write => "W",
+      };
+      writeln!(
+        f,
+        "{}: {} {}{} {}={}",
+        name(id),
+        processor,
+        dir,
+        if event.locked { "*" } else { "" },
+        event.memloc,
+        event.value
+      )?;
+    }
+    let relations = [
+      ("po", &self.po),
+      ("rf", &self.rf),
+      ("co", &self.co),
+      ("fr", &self.fr),
+    ];
+    for (i, &(label, pairs)) in relations.iter().enumerate() {
+      let pairs: Vec<String> = pairs
+        .iter()
+        .map(|&(a, b)| format!("{}->{}", name(a), name(b)))
+        .collect();
+      write!(f, "{}: {}", label, pairs.join(" "))?;
+      if i + 1 < relations.len() {
+        writeln!(f)?;
+      }
+    }
+    Ok(())
+  }
+}
@@ -1,49 +1,152 @@
+// first, so that the other modules can use its litmus! macro.
+#[macro_use]
+pub mod dsl;
 pub mod ast;
+pub mod axiomatic;
+pub mod catalogue;
+pub mod check;
+pub mod config;
+pub mod desugar;
+pub mod diy;
+pub mod dot;
+pub mod fingerprint;
+pub mod fuzz;
+pub mod graph;
+pub mod model;
+pub mod parser;
 pub mod state;
-mod desugar;
+pub mod trace;
 mod run;
 
 use ast::{Prog, Pred, PredType};
-use desugar::desugar;
-use run::run;
-use state::State;
+use axiomatic::axiomatic;
+use check::{CheckResult, CrossCheck, Relaxed};
+use config::Config;
+use desugar::{desugar, DesugarError};
+use dot::StateGraph;
+use graph::Graph;
+use model::{Sc, Tso};
+use run::{run, explore};
+use state::{State, Terminal};
 
 pub fn litmus(
-  name: &'static str,
+  name: &str,
   prog: &Prog,
   init: State,
   pred: &Pred,
   pred_type: PredType,
-) -> bool {
-  let desugared = desugar(prog);
-  let terminals = run(name, desugared, init);
-
-  assert!(terminals.len() > 0);
-
-  match pred_type {
-    PredType::Forbidden => {
-      for terminal in &terminals {
-        if terminal.satisfy(pred) {
-          return false;
-        }
-      }
-      return true;
-    },
-    PredType::Required => {
-      for terminal in &terminals {
-        if !terminal.satisfy(pred) {
-          return false;
-        }
-      }
-      return true;
-    },
-    PredType::Allowed => {
-      for terminal in &terminals {
-        if terminal.satisfy(pred) {
-          return true;
-        }
-      }
-      return false;
-    },
-  }
+) -> Result<CheckResult, DesugarError> {
+  litmus_with_config(name, prog, init, pred, pred_type, &Config::default())
+}
+
+pub fn litmus_with_config(
+  name: &str,
+  prog: &Prog,
+  init: State,
+  pred: &Pred,
+  pred_type: PredType,
+  config: &Config,
+) -> Result<CheckResult, DesugarError> {
+  let desugared = desugar(prog)?;
+  let exploration = run(name, desugared, init, config);
+
+  Ok(CheckResult {
+    collision: exploration.collision,
+    ..CheckResult::new(
+      name,
+      exploration.terminals,
+      pred,
+      pred_type,
+      exploration.states,
+      exploration.transitions,
+      exploration.truncated,
+    )
+  })
+}
+
+// Explores prog under SC and under the memory model of config, reporting
+// the outcomes the latter adds.
+pub fn relaxed(
+  name: &str,
+  prog: &Prog,
+  init: State,
+  config: &Config,
+) -> Result<Relaxed, DesugarError> {
+  let desugared = desugar(prog)?;
+  let sc_config = Config { model: &Sc, ..config.clone() };
+  let sc = run(name, desugared.clone(), init.clone(), &sc_config);
+  let exploration = run(name, desugared, init, config);
+
+  let mut truncated = sc.truncated;
+  truncated.extend(exploration.truncated);
+  Ok(Relaxed::new(
+    name,
+    config.model.name(),
+    sc.terminals,
+    exploration.terminals,
+    truncated,
+  ))
+}
+
+// Explores prog under the operational x86-TSO model and enumerates its
+// executions under the axiomatic one, ignoring the model of config.
+pub fn cross_check(
+  name: &str,
+  prog: &Prog,
+  init: State,
+  config: &Config,
+) -> Result<CrossCheck, DesugarError> {
+  let desugared = desugar(prog)?;
+  let axiomatic = axiomatic(&desugared, &init, config);
+  let config = Config { model: &Tso, ..config.clone() };
+  let exploration = run(name, desugared, init, &config);
+
+  Ok(CrossCheck::new(
+    name,
+    exploration.terminals,
+    axiomatic.outcomes,
+    exploration.truncated,
+    axiomatic.truncated,
+  ))
+}
+
+// Explores prog under the memory model of config and rebuilds the execution
+// graph of a shortest execution reaching each distinct outcome, in ascending
+// order of outcomes.
+pub fn graphs(
+  name: &str,
+  prog: &Prog,
+  init: State,
+  config: &Config,
+) -> Result<Vec<(Terminal, Graph)>, DesugarError> {
+  let desugared = desugar(prog)?;
+  let mut terminals =
+    run(name, desugared.clone(), init.clone(), config).terminals;
+
+  // the sort is stable, so the shortest trace of each outcome survives.
+  terminals.sort_by(|a, b| a.0.cmp(&b.0));
+  terminals.dedup_by(|a, b| a.0 == b.0);
+  Ok(
+    terminals
+      .into_iter()
+      .map(|(terminal, trace)| {
+        let graph = Graph::replay(&desugared, &init, &trace, config.model);
+        (terminal, graph)
+      })
+      .collect(),
+  )
+}
+
+// Explores prog under the memory model of config, returning every state and
+// transition visited.
+pub fn state_graph(
+  name: &str,
+  prog: &Prog,
+  init: State,
+  config: &Config,
+) -> Result<StateGraph, DesugarError> {
+  let desugared = desugar(prog)?;
+  let mut graph = StateGraph::default();
+  explore(name, desugared, init, config, Some(&mut graph));
+  Ok(graph)
 }
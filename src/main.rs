@@ -0,0 +1,295 @@
+extern crate x86_litmus;
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
+use std::time::Instant;
+use x86_litmus::config::Config;
+use x86_litmus::diy::{generate, parse_cycle};
+use x86_litmus::fuzz::{fuzz, Limits};
+use x86_litmus::model::{MemoryModel, Sc, Tso};
+use x86_litmus::parser::{parse, Litmus};
+use x86_litmus::{cross_check, graphs, litmus_with_config, relaxed};
+use x86_litmus::state_graph;
+
+const USAGE: &str = "usage: x86-litmus [OPTION]... [FILE]...
+
+Model-checks each herd-style X86 litmus FILE under x86-TSO or another
+memory model, printing its outcomes and verdict. Exits with status 1 if any
+test does not meet its expectation, and with status 2 if any file cannot be
+read, parsed or desugared.
+
+Options:
+  --model MODEL       check under MODEL, either tso (default) or sc
+  --max-iterations N  take at most N backward jumps on each processor
+  --max-storebuf N    buffer at most N pending writes on each processor
+  --max-steps N       explore executions of at most N steps
+  --por               apply partial-order reduction
+  --threads N         explore the state space with N threads
+  --fingerprints      remember visited states by 64-bit fingerprints
+  --relaxed           also list the outcomes not allowed under SC
+  --graphs            also print an execution graph of each outcome
+  --dot DIR           also write the state graph of each test to DIR/NAME.dot
+  --cross-check       also compare with the axiomatic x86-TSO model, failing
+                      the test if the outcomes differ
+  --cycle CYCLE       also check the test generated from the relaxation
+                      CYCLE, e.g. \"PodWR Fre PodWR Fre\"
+  --fuzz N            also check N random programs against SC, the axiomatic
+                      model and the exploration options, writing each
+                      discrepancy found as a minimized test
+  --seed N            generate the random programs from seed N (default 0)
+  --fuzz-dir DIR      write the tests --fuzz finds to DIR/NAME.litmus
+                      rather than the current directory";
+
+// Command-line options.
+#[derive(Default)]
+struct Options {
+  config: Config,
+  // print the usage and exit.
+  help: bool,
+  // report relaxed outcomes after each result.
+  relaxed: bool,
+  // print execution graphs after each result.
+  graphs: bool,
+  // directory to write state graphs to.
+  dot: Option<String>,
+  // compare operational and axiomatic outcomes after each result.
+  cross_check: bool,
+  // relaxation cycles to generate tests from.
+  cycles: Vec<String>,
+  // number of random programs to check, the seed of the first and the
+  // directory to write discrepancies to.
+  fuzz: Option<usize>,
+  seed: u64,
+  fuzz_dir: Option<String>,
+  paths: Vec<String>,
+}
+
+fn load(path: &str) -> Result<Litmus, String> {
+  let text = fs::read_to_string(path).map_err(
+    |e| format!("{}: {}", path, e),
+  )?;
+  parse(&text).map_err(|e| format!("{}: {}", path, e))
+}
+
+// Checks a single test, returning whether it meets its expectation and, if
+// cross-checking, whether the models agree.
+fn check(test: Litmus, options: &Options) -> Result<bool, String> {
+  let name = &test.name;
+  let start = Instant::now();
+  let result = litmus_with_config(
+    &test.name,
+    &test.prog,
+    test.init.clone(),
+    &test.pred,
+    test.pred_type,
+    &options.config,
+  ).map_err(|e| format!("{}: {}", name, e))?;
+  let elapsed = start.elapsed();
+
+  println!("{}", result);
+  if options.relaxed {
+    let report =
+      relaxed(&test.name, &test.prog, test.init.clone(), &options.config)
+        .map_err(|e| format!("{}: {}", name, e))?;
+    println!("{}", report);
+  }
+  if options.graphs {
+    let graphs =
+      graphs(&test.name, &test.prog, test.init.clone(), &options.config)
+        .map_err(|e| format!("{}: {}", name, e))?;
+    for (terminal, graph) in graphs {
+      println!("Graph {}\n{}", terminal, graph);
+    }
+  }
+  if let Some(ref dir) = options.dot {
+    let graph =
+      state_graph(&test.name, &test.prog, test.init.clone(), &options.config)
+        .map_err(|e| format!("{}: {}", name, e))?;
+    let path = Path::new(dir).join(format!("{}.dot", test.name));
+    fs::write(&path, graph.to_dot(&test.name))
+      .map_err(|e| format!("{}: {}", path.display(), e))?;
+  }
+  let mut agrees = true;
+  if options.cross_check {
+    let report = cross_check(&test.name, &test.prog, test.init, &options.config)
+      .map_err(|e| format!("{}: {}", name, e))?;
+    println!("{}", report);
+    agrees = report.agrees();
+  }
+  println!(
+    "Time {} {}.{:03}",
+    test.name,
+    elapsed.as_secs(),
+    elapsed.subsec_millis()
+  );
+  println!();
+
+  Ok(result.verdict && agrees)
+}
+
+// Splits the command line into options and test files.
+fn parse_args(args: &[String]) -> Result<Options, String> {
+  let mut options = Options {
+    config: Config { progress: true, ..Config::default() },
+    ..Options::default()
+  };
+  let config = &mut options.config;
+  let mut args = args.iter();
+
+  while let Some(arg) = args.next() {
+    let flag = match arg.as_str() {
+      "-h" | "--help" => Some(&mut options.help),
+      "--relaxed" => Some(&mut options.relaxed),
+      "--graphs" => Some(&mut options.graphs),
+      "--cross-check" => Some(&mut options.cross_check),
+      "--por" => Some(&mut config.por),
+      "--fingerprints" => Some(&mut config.fingerprints),
+      _ => None,
+    };
+    if let Some(flag) = flag {
+      *flag = true;
+      continue;
+    }
+    if arg == "--dot" {
+      let dir = args.next().ok_or("`--dot` expects a directory")?;
+      options.dot = Some(dir.clone());
+      continue;
+    }
+    if arg == "--cycle" {
+      let cycle = args.next().ok_or("`--cycle` expects a cycle")?;
+      options.cycles.push(cycle.clone());
+      continue;
+    }
+    if arg == "--fuzz-dir" {
+      let dir = args.next().ok_or("`--fuzz-dir` expects a directory")?;
+      options.fuzz_dir = Some(dir.clone());
+      continue;
+    }
+    if arg == "--fuzz" {
+      let count = args.next().and_then(|count| count.parse().ok());
+      options.fuzz = Some(count.ok_or("`--fuzz` expects a number")?);
+      continue;
+    }
+    if arg == "--seed" {
+      let seed = args.next().and_then(|seed| seed.parse().ok());
+      options.seed = seed.ok_or("`--seed` expects a number")?;
+      continue;
+    }
+    if arg == "--model" {
+      config.model = match args.next().map(|model| model.as_str()) {
+        Some("tso") => &Tso,
+        Some("sc") => &Sc,
+        _ => return Err("`--model` expects `tso` or `sc`".to_string()),
+      };
+      continue;
+    }
+    if arg == "--threads" {
+      let threads = args.next().and_then(|threads| threads.parse().ok());
+      config.threads = match threads {
+        Some(threads) if threads > 0 => threads,
+        _ => return Err("`--threads` expects a positive number".to_string()),
+      };
+      continue;
+    }
+    let bound = match arg.as_str() {
+      "--max-iterations" => &mut config.max_iterations,
+      "--max-storebuf" => &mut config.max_storebuf,
+      "--max-steps" => &mut config.max_steps,
+      _ if arg.starts_with("--") => {
+        return Err(format!("unknown option `{}`", arg));
+      },
+      _ => {
+        options.paths.push(arg.clone());
+        continue;
+      },
+    };
+    let value = args.next().ok_or(format!("`{}` expects a number", arg))?;
+    let value = value.parse().map_err(
+      |_| format!("`{}` expects a number, found `{}`", arg, value),
+    )?;
+    *bound = Some(value);
+  }
+  Ok(options)
+}
+
+fn main() {
+  let args: Vec<String> = env::args().skip(1).collect();
+
+  let options = match parse_args(&args) {
+    Ok(parsed) => parsed,
+    Err(e) => {
+      eprintln!("error: {}", e);
+      process::exit(2);
+    },
+  };
+
+  if options.help {
+    println!("{}", USAGE);
+    process::exit(0);
+  }
+  let paths = &options.paths;
+  let cycles = &options.cycles;
+  let fuzzing = options.fuzz.is_some();
+  if paths.is_empty() && cycles.is_empty() && !fuzzing {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+  }
+
+  let mut tests: Vec<Result<Litmus, String>> =
+    paths.iter().map(|path| load(path)).collect();
+  for cycle in cycles {
+    tests.push(parse_cycle(cycle).and_then(|cycle| generate(&cycle)).map_err(
+      |e| format!("`{}`: {}", cycle, e),
+    ));
+  }
+  let mut total = tests.len();
+  let mut failed = 0;
+  let mut broken = 0;
+  for test in tests {
+    match test.and_then(|test| check(test, &options)) {
+      Ok(true) => (),
+      Ok(false) => failed += 1,
+      Err(e) => {
+        eprintln!("error: {}", e);
+        broken += 1;
+      },
+    }
+  }
+
+  if let Some(count) = options.fuzz {
+    total += count;
+    let counterexamples =
+      fuzz(options.seed, count, &Limits::default(), &options.config);
+    let dir = Path::new(options.fuzz_dir.as_deref().unwrap_or("."));
+    for counterexample in counterexamples {
+      let test = &counterexample.test;
+      println!("Discrepancy {}: {}", test.name, counterexample.discrepancy);
+      println!("{}", test);
+      failed += 1;
+      let path = dir.join(format!("{}.litmus", test.name));
+      if let Err(e) = fs::write(&path, format!("{}\n", test)) {
+        eprintln!("error: {}: {}", path.display(), e);
+        broken += 1;
+      }
+      // the saved test fails under the same model and with the option that
+      // caused the discrepancy.
+      let mut command = vec!["x86-litmus".to_string()];
+      if options.config.model.name() == Sc.name() {
+        command.push("--model sc".to_string());
+      }
+      command.extend(counterexample.discrepancy.check.flag().map(String::from));
+      command.push(path.display().to_string());
+      println!("Reproduce with {}\n", command.join(" "));
+    }
+  }
+
+  if broken > 0 {
+    process::exit(2);
+  }
+  if failed > 0 {
+    eprintln!("{} of {} tests failed", failed, total);
+    process::exit(1);
+  }
+}
@@ -0,0 +1,292 @@
+use ast::{CoreProg, CoreInst, MemLoc, Proc, Value};
+use run::{mov, alu, read, fence, lock, unlock, branch};
+use run::{extract, increase_ip};
+use state::State;
+use std::any::Any;
+use std::collections::VecDeque;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use trace::Rule;
+
+// A rule of the abstract machine: the state reached when processor takes
+// the rule from state, if enabled.
+pub type Transition = fn(Proc, &CoreProg, &State) -> Option<State>;
+
+// Memory model given by the transition rules of an abstract machine and the
+// state each processor keeps under it, such as a store buffer.
+pub trait MemoryModel: Sync {
+  fn name(&self) -> &'static str;
+
+  // rules tried, in order, for every processor in every state.
+  fn rules(&self) -> &'static [(Rule, Transition)];
+
+  // Whether transitions by rule only touch the state of their processor and
+  // commute with every transition of the other processors, so that
+  // exploring one of them alone still reaches every terminal state.
+  fn is_local(&self, rule: Rule) -> bool;
+
+  // model state of every processor before its first step.
+  fn init(&self) -> Arc<dyn ModelState>;
+}
+
+// State a memory model keeps for a processor besides its registers, flags
+// and instruction pointer, in ProcState::model. The rules of the model see
+// its own type, the rules shared by all models only these methods.
+pub trait ModelState: AnyState + fmt::Debug + fmt::Display + Send + Sync {
+  // number of writes of the processor not yet in memory. A processor only
+  // fences, unlocks or finishes once it has none.
+  fn pending(&self) -> usize;
+
+  // value of the latest pending write to memloc, which the processor reads
+  // instead of memory.
+  fn forward(&self, memloc: MemLoc) -> Option<Value>;
+}
+
+// Comparison and hashing of model states of any type behind a ModelState.
+pub trait AnyState {
+  fn as_any(&self) -> &dyn Any;
+  fn eq_any(&self, other: &dyn Any) -> bool;
+  fn hash_any(&self, hasher: &mut dyn Hasher);
+}
+
+impl<T: Any + Eq + Hash> AnyState for T {
+  fn as_any(&self) -> &dyn Any {
+    self
+  }
+
+  fn eq_any(&self, other: &dyn Any) -> bool {
+    other.downcast_ref::<T>() == Some(self)
+  }
+
+  fn hash_any(&self, mut hasher: &mut dyn Hasher) {
+    self.hash(&mut hasher);
+  }
+}
+
+impl dyn ModelState {
+  // the state as the type of its model, None if it is of another.
+  pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+    self.as_any().downcast_ref()
+  }
+}
+
+impl PartialEq for dyn ModelState {
+  fn eq(&self, other: &Self) -> bool {
+    self.eq_any(other.as_any())
+  }
+}
+
+impl Eq for dyn ModelState {}
+
+impl Hash for dyn ModelState {
+  fn hash<H: Hasher>(&self, hasher: &mut H) {
+    self.hash_any(hasher);
+  }
+}
+
+// x86-TSO: writes are buffered per processor and reach memory in order.
+#[derive(Clone, Copy, Debug)]
+pub struct Tso;
+
+// Pending writes of an x86-TSO processor, oldest first.
+#[derive(PartialEq, Eq, Hash, Clone, Default, Debug)]
+pub struct StoreBuffer(pub VecDeque<(MemLoc, Value)>);
+
+// Sequential consistency: writes go straight to memory.
+#[derive(Clone, Copy, Debug)]
+pub struct Sc;
+
+// Model state of an SC processor, which keeps nothing beyond its registers.
+// Processors also start out with it until an exploration sets the state of
+// its model.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Default, Debug)]
+pub struct NoState;
+
+static TSO: [(Rule, Transition); 9] = [
+  (Rule::Mov, mov),
+  (Rule::Alu, alu),
+  (Rule::Read, read),
+  (Rule::Write, write),
+  (Rule::Tau, tau),
+  (Rule::Fence, fence),
+  (Rule::Lock, lock),
+  (Rule::Unlock, unlock),
+  (Rule::Branch, branch),
+];
+
+// nothing is ever pending, so reads, fences and unlocks behave as under TSO
+// and there is nothing for tau to flush.
+static SC: [(Rule, Transition); 8] = [
+  (Rule::Mov, mov),
+  (Rule::Alu, alu),
+  (Rule::Read, read),
+  (Rule::Write, write_through),
+  (Rule::Fence, fence),
+  (Rule::Lock, lock),
+  (Rule::Unlock, unlock),
+  (Rule::Branch, branch),
+];
+
+// location and value of the write at the ip of processor, if any.
+fn written(
+  processor: Proc,
+  prog: &CoreProg,
+  state: &State,
+) -> Option<(MemLoc, Value)> {
+  let (proc_prog, proc_state, proc_ip) = extract(processor, prog, state)?;
+
+  match proc_prog[proc_ip] {
+    CoreInst::Write1(memloc, reg) => Some((memloc, proc_state.get(reg))),
+    CoreInst::Write2(memloc, value) => Some((memloc, value)),
+    _ => None,
+  }
+}
+
+// store buffer of processor, which every processor has under x86-TSO.
+fn storebuf(state: &State, processor: Proc) -> &StoreBuffer {
+  state.procs[&processor].model.downcast_ref().unwrap()
+}
+
+// write to the store buffer.
+fn write(processor: Proc, prog: &CoreProg, state: &State) -> Option<State> {
+  let (memloc, value) = written(processor, prog, state)?;
+  let mut storebuf = storebuf(state, processor).clone();
+  storebuf.0.push_back((memloc, value));
+
+  let mut nstate = state.clone();
+  increase_ip(processor, prog.0[&processor].len(), &mut nstate);
+  nstate.procs.get_mut(&processor).unwrap().model = Arc::new(storebuf);
+  Some(nstate)
+}
+
+// flush the oldest write of the store buffer to memory.
+fn tau(processor: Proc, _: &CoreProg, state: &State) -> Option<State> {
+  if state.is_blocked(processor) {
+    return None;
+  }
+  let mut storebuf = storebuf(state, processor).clone();
+  let (memloc, value) = storebuf.0.pop_front()?;
+
+  let mut nstate = state.clone();
+  nstate.procs.get_mut(&processor).unwrap().model = Arc::new(storebuf);
+  nstate.mem.insert(memloc, value);
+  Some(nstate)
+}
+
+// write value straight to memory.
+fn write_through(
+  processor: Proc,
+  prog: &CoreProg,
+  state: &State,
+) -> Option<State> {
+  let (memloc, value) = written(processor, prog, state)?;
+  if state.is_blocked(processor) {
+    return None;
+  }
+  let mut nstate = state.clone();
+  increase_ip(processor, prog.0[&processor].len(), &mut nstate);
+
+  nstate.mem.insert(memloc, value);
+  Some(nstate)
+}
+
+impl ModelState for StoreBuffer {
+  fn pending(&self) -> usize {
+    self.0.len()
+  }
+
+  fn forward(&self, memloc: MemLoc) -> Option<Value> {
+    self
+      .0
+      .iter()
+      .rev()
+      .find(|&&(written, _)| written == memloc)
+      .map(|&(_, value)| value)
+  }
+}
+
+// Printed as the pending writes, oldest first, e.g. `x=1 y=2`.
+impl fmt::Display for StoreBuffer {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let writes: Vec<String> = self
+      .0
+      .iter()
+      .map(|&(memloc, value)| format!("{}={}", memloc, value))
+      .collect();
+    write!(f, "{}", writes.join(" "))
+  }
+}
+
+impl ModelState for NoState {
+  fn pending(&self) -> usize {
+    0
+  }
+
+  fn forward(&self, _: MemLoc) -> Option<Value> {
+    None
+  }
+}
+
+impl fmt::Display for NoState {
+  fn fmt(&self, _: &mut fmt::Formatter) -> fmt::Result {
+    Ok(())
+  }
+}
+
+impl MemoryModel for Tso {
+  fn name(&self) -> &'static str {
+    "x86-TSO"
+  }
+
+  fn rules(&self) -> &'static [(Rule, Transition)] {
+    &TSO
+  }
+
+  // register computation and branches are private, a buffered write is
+  // invisible until flushed, a fence stays enabled once the buffer is empty,
+  // and no other processor can take the lock before its owner unlocks.
+  fn is_local(&self, rule: Rule) -> bool {
+    match rule {
+      Rule::Mov |
+      Rule::Alu |
+      Rule::Branch |
+      Rule::Write |
+      Rule::Fence |
+      Rule::Unlock => true,
+      Rule::Read | Rule::Tau | Rule::Lock => false,
+    }
+  }
+
+  fn init(&self) -> Arc<dyn ModelState> {
+    Arc::new(StoreBuffer::default())
+  }
+}
+
+impl MemoryModel for Sc {
+  fn name(&self) -> &'static str {
+    "SC"
+  }
+
+  fn rules(&self) -> &'static [(Rule, Transition)] {
+    &SC
+  }
+
+  // as under TSO, except that writes go straight to memory.
+  fn is_local(&self, rule: Rule) -> bool {
+    match rule {
+      Rule::Mov | Rule::Alu | Rule::Branch | Rule::Fence | Rule::Unlock => true,
+      Rule::Read | Rule::Write | Rule::Tau | Rule::Lock => false,
+    }
+  }
+
+  fn init(&self) -> Arc<dyn ModelState> {
+    Arc::new(NoState)
+  }
+}
+
+impl fmt::Debug for dyn MemoryModel {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}", self.name())
+  }
+}
@@ -0,0 +1,797 @@
+use ast::{Value, Proc, MemLoc, Reg, Operand, AluOp, UnaryOp, Label, Cond};
+use ast::{Inst, Prog, Pred, PredType, Expr, Rel};
+use state::State;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+
+// A litmus test read from herd-style `.litmus` text.
+pub struct Litmus {
+  pub name: String,
+  pub prog: Prog,
+  pub init: State,
+  pub pred: Pred,
+  pub pred_type: PredType,
+}
+
+#[derive(Debug)]
+pub struct ParseError {
+  // 1-based line number in the source text.
+  pub line: usize,
+  pub message: String,
+}
+
+impl fmt::Display for ParseError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "line {}: {}", self.line, self.message)
+  }
+}
+
+impl Error for ParseError {}
+
+// Printed in the herd format parse reads, one column per processor.
+impl fmt::Display for Litmus {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    writeln!(f, "X86 {}", self.name)?;
+    write!(f, "{{")?;
+    for (processor, proc_state) in &self.init.procs {
+      for (reg, value) in proc_state.regs.iter() {
+        write!(f, " {}:{}={};", processor.0, reg, value)?;
+      }
+    }
+    for (memloc, value) in self.init.mem.iter() {
+      write!(f, " {}={};", memloc, value)?;
+    }
+    writeln!(f, " }}")?;
+
+    let mut columns: Vec<Vec<String>> = self
+      .prog
+      .0
+      .iter()
+      .map(|(processor, insts)| {
+        let mut column = vec![processor.to_string()];
+        column.extend(insts.iter().map(|inst| inst.to_string()));
+        column
+      })
+      .collect();
+    let rows = columns.iter().map(|column| column.len()).max().unwrap_or(0);
+    for column in &mut columns {
+      let width = column.iter().map(|cell| cell.len()).max().unwrap_or(0);
+      column.resize(rows, String::new());
+      for cell in column.iter_mut() {
+        *cell = format!(" {:1$} ", cell, width);
+      }
+    }
+    for row in 0..rows {
+      let cells: Vec<&str> =
+        columns.iter().map(|column| column[row].as_str()).collect();
+      writeln!(f, "{};", cells.join("|"))?;
+    }
+    write!(f, "{} {}", self.pred_type, self.pred)
+  }
+}
+
+#[derive(Clone, PartialEq)]
+enum Token {
+  Ident(String),
+  Num(i32),
+  Sym(&'static str),
+}
+
+impl fmt::Display for Token {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Token::Ident(ref ident) => write!(f, "`{}`", ident),
+      Token::Num(num) => write!(f, "`{}`", num),
+      Token::Sym(sym) => write!(f, "`{}`", sym),
+    }
+  }
+}
+
+// longer symbols come first so that they win over their prefixes.
+static SYMBOLS: [&str; 17] = [
+  "/\\", "\\/", "=>", "!=", "<=", ">=", "[", "]", "(", ")", ",", ":", "=",
+  "<", ">", "$", "~",
+];
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+  let mut tokens = vec![];
+  let mut rest = text;
+
+  'outer: loop {
+    rest = rest.trim_start();
+    let c = match rest.chars().next() {
+      Some(c) => c,
+      None => break,
+    };
+
+    if c.is_ascii_alphabetic() || c == '_' {
+      let len = rest
+        .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .unwrap_or(rest.len());
+      tokens.push(Token::Ident(rest[..len].to_string()));
+      rest = &rest[len..];
+      continue;
+    }
+    if c.is_ascii_digit() {
+      let len = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(
+        rest.len(),
+      );
+      let num = rest[..len].parse::<i32>().map_err(|_| {
+        format!("number `{}` is out of range", &rest[..len])
+      })?;
+      tokens.push(Token::Num(num));
+      rest = &rest[len..];
+      continue;
+    }
+    // a minus sign only ever prefixes a number.
+    if c == '-' {
+      let len = 1 +
+        rest[1..].find(|c: char| !c.is_ascii_digit()).unwrap_or(
+          rest.len() - 1,
+        );
+      let num = rest[..len].parse::<i32>().map_err(|_| {
+        format!("invalid number `{}`", &rest[..len])
+      })?;
+      tokens.push(Token::Num(num));
+      rest = &rest[len..];
+      continue;
+    }
+    for sym in SYMBOLS.iter() {
+      if rest.starts_with(sym) {
+        tokens.push(Token::Sym(sym));
+        rest = &rest[sym.len()..];
+        continue 'outer;
+      }
+    }
+    return Err(format!("unexpected character `{}`", c));
+  }
+  Ok(tokens)
+}
+
+struct Parser {
+  tokens: Vec<Token>,
+  pos: usize,
+}
+
+impl Parser {
+  fn new(text: &str) -> Result<Self, String> {
+    Ok(Parser {
+      tokens: tokenize(text)?,
+      pos: 0,
+    })
+  }
+
+  fn peek(&self) -> Option<&Token> {
+    self.tokens.get(self.pos)
+  }
+
+  fn next(&mut self) -> Result<Token, String> {
+    let token = self.tokens.get(self.pos).cloned().ok_or_else(|| {
+      "unexpected end of input".to_string()
+    })?;
+    self.pos += 1;
+    Ok(token)
+  }
+
+  fn is_done(&self) -> bool {
+    self.pos == self.tokens.len()
+  }
+
+  fn eat(&mut self, sym: &'static str) -> bool {
+    if self.peek() == Some(&Token::Sym(sym)) {
+      self.pos += 1;
+      true
+    } else {
+      false
+    }
+  }
+
+  fn expect(&mut self, sym: &'static str) -> Result<(), String> {
+    match self.next()? {
+      Token::Sym(s) if s == sym => Ok(()),
+      token => Err(format!("expected `{}`, found {}", sym, token)),
+    }
+  }
+
+  fn expect_end(&self) -> Result<(), String> {
+    match self.peek() {
+      None => Ok(()),
+      Some(token) => Err(format!("unexpected {}", token)),
+    }
+  }
+
+  fn ident(&mut self) -> Result<String, String> {
+    match self.next()? {
+      Token::Ident(ident) => Ok(ident),
+      token => Err(format!("expected identifier, found {}", token)),
+    }
+  }
+
+  fn num(&mut self) -> Result<i32, String> {
+    match self.next()? {
+      Token::Num(num) => Ok(num),
+      token => Err(format!("expected number, found {}", token)),
+    }
+  }
+
+  // value ::= '$'? num
+  fn value(&mut self) -> Result<Value, String> {
+    self.eat("$");
+    Ok(Value(self.num()?))
+  }
+
+  // memloc ::= '[' ident ']' | ident
+  fn memloc(&mut self) -> Result<MemLoc, String> {
+    if self.eat("[") {
+      let name = self.ident()?;
+      self.expect("]")?;
+      Ok(MemLoc::intern(&name))
+    } else {
+      Ok(MemLoc::intern(&self.ident()?))
+    }
+  }
+
+  // operand ::= '[' ident ']' | '$'? num | reg
+  fn operand(&mut self) -> Result<Operand, String> {
+    match self.peek().cloned() {
+      Some(Token::Sym("[")) => Ok(Operand::MemLoc(self.memloc()?)),
+      Some(Token::Sym("$")) |
+      Some(Token::Num(_)) => Ok(Operand::Imm(self.value()?)),
+      Some(Token::Ident(_)) => Ok(Operand::Reg(parse_reg(&self.ident()?)?)),
+      Some(token) => Err(format!("expected operand, found {}", token)),
+      None => Err("expected operand".to_string()),
+    }
+  }
+
+  // insts ::= (ident ':')* inst?
+  fn insts(&mut self) -> Result<Vec<Inst>, String> {
+    let mut insts = vec![];
+
+    while let (Some(Token::Ident(name)), Some(&Token::Sym(":"))) =
+      (self.peek().cloned(), self.tokens.get(self.pos + 1))
+    {
+      self.pos += 2;
+      insts.push(Inst::Label(Label::intern(&name)));
+    }
+    if !self.is_done() {
+      insts.push(self.inst()?);
+    }
+    Ok(insts)
+  }
+
+  // inst ::= 'LOCK'? mnemonic (operand (',' operand)*)?
+  //        | ('JMP' | 'J' cond) ident
+  fn inst(&mut self) -> Result<Inst, String> {
+    let mut mnemonic = self.ident()?.to_uppercase();
+    let locked = mnemonic == "LOCK";
+    let mut operands = vec![];
+
+    if locked {
+      mnemonic = self.ident()?.to_uppercase();
+    }
+    let jump = if mnemonic == "JMP" {
+      Some(None)
+    } else {
+      mnemonic.strip_prefix('J').and_then(parse_cond).map(Some)
+    };
+    if let Some(cond) = jump {
+      let label = Label::intern(&self.ident()?);
+      self.expect_end()?;
+      if locked {
+        return Err(format!("`LOCK` cannot prefix `{}`", mnemonic));
+      }
+      return Ok(match cond {
+        Some(cond) => Inst::Jcc(cond, label),
+        None => Inst::Jmp(label),
+      });
+    }
+    if !self.is_done() {
+      operands.push(self.operand()?);
+      while self.eat(",") {
+        operands.push(self.operand()?);
+      }
+    }
+    self.expect_end()?;
+
+    // SETcc and CMOVcc spell their condition after the mnemonic.
+    let mut base = mnemonic.as_str();
+    let mut cond = Cond::E;
+    for prefix in &["SET", "CMOV"] {
+      if let Some(c) = mnemonic.strip_prefix(prefix).and_then(parse_cond) {
+        base = prefix;
+        cond = c;
+      }
+    }
+
+    let arity = match base {
+      "MFENCE" => 0,
+      "INC" | "DEC" | "NEG" | "SET" => 1,
+      "MOV" | "XCHG" | "ADD" | "SUB" | "AND" | "OR" | "XOR" | "XADD" |
+      "CMPXCHG" | "CMP" | "TEST" | "CMOV" => 2,
+      _ => return Err(format!("unknown instruction `{}`", mnemonic)),
+    };
+    if operands.len() != arity {
+      return Err(format!("wrong number of operands for `{}`", mnemonic));
+    }
+
+    let inst = match base {
+      "MFENCE" => Inst::Mfence,
+      "INC" => Inst::Unary(UnaryOp::Inc, operands[0]),
+      "DEC" => Inst::Unary(UnaryOp::Dec, operands[0]),
+      "NEG" => Inst::Unary(UnaryOp::Neg, operands[0]),
+      "MOV" => Inst::Mov(operands[0], operands[1]),
+      "XCHG" => Inst::Xchg(operands[0], operands[1]),
+      "ADD" => Inst::Alu(AluOp::Add, operands[0], operands[1]),
+      "SUB" => Inst::Alu(AluOp::Sub, operands[0], operands[1]),
+      "AND" => Inst::Alu(AluOp::And, operands[0], operands[1]),
+      "OR" => Inst::Alu(AluOp::Or, operands[0], operands[1]),
+      "XOR" => Inst::Alu(AluOp::Xor, operands[0], operands[1]),
+      "XADD" => Inst::Xadd(operands[0], operands[1]),
+      "CMPXCHG" => Inst::Cmpxchg(operands[0], operands[1]),
+      "CMP" => Inst::Cmp(operands[0], operands[1]),
+      "TEST" => Inst::Test(operands[0], operands[1]),
+      "SET" => Inst::Setcc(cond, operands[0]),
+      "CMOV" => Inst::Cmovcc(cond, operands[0], operands[1]),
+      _ => unreachable!(),
+    };
+    if !locked {
+      return Ok(inst);
+    }
+
+    match inst {
+      Inst::Alu(op, operand1, operand2) => {
+        Ok(Inst::LockAlu(op, operand1, operand2))
+      },
+      Inst::Unary(op, operand) => Ok(Inst::LockUnary(op, operand)),
+      Inst::Xadd(operand1, operand2) => Ok(Inst::LockXadd(operand1, operand2)),
+      Inst::Cmpxchg(operand1, operand2) => {
+        Ok(Inst::LockCmpxchg(operand1, operand2))
+      },
+      // xchg with memory always locks the bus.
+      Inst::Xchg(..) => Ok(inst),
+      _ => Err(format!("`LOCK` cannot prefix `{}`", mnemonic)),
+    }
+  }
+
+  // location ::= num ':' reg | memloc
+  fn location(&mut self) -> Result<Location, String> {
+    match self.peek().cloned() {
+      Some(Token::Num(num)) => {
+        self.pos += 1;
+        self.expect(":")?;
+        Ok(Location::Reg(parse_proc_id(num)?, parse_reg(&self.ident()?)?))
+      },
+      _ => Ok(Location::MemLoc(self.memloc()?)),
+    }
+  }
+
+  // expr ::= location | '$'? num
+  fn expr(&mut self) -> Result<Expr, String> {
+    match (self.peek().cloned(), self.tokens.get(self.pos + 1)) {
+      (Some(Token::Num(_)), Some(&Token::Sym(":"))) |
+      (Some(Token::Ident(_)), _) |
+      (Some(Token::Sym("[")), _) => {
+        Ok(match self.location()? {
+          Location::Reg(processor, reg) => Expr::Reg(processor, reg),
+          Location::MemLoc(memloc) => Expr::MemLoc(memloc),
+        })
+      },
+      _ => Ok(Expr::Value(self.value()?)),
+    }
+  }
+
+  fn rel(&mut self) -> Result<Rel, String> {
+    match self.next()? {
+      Token::Sym("=") => Ok(Rel::Eq),
+      Token::Sym("!=") => Ok(Rel::Ne),
+      Token::Sym("<") => Ok(Rel::Lt),
+      Token::Sym("<=") => Ok(Rel::Le),
+      Token::Sym(">") => Ok(Rel::Gt),
+      Token::Sym(">=") => Ok(Rel::Ge),
+      token => Err(format!("expected comparison, found {}", token)),
+    }
+  }
+
+  // atom ::= '(' pred ')' | 'true' | 'false' | expr rel expr
+  fn atom(&mut self) -> Result<Pred, String> {
+    if self.eat("(") {
+      let pred = self.pred()?;
+      self.expect(")")?;
+      return Ok(pred);
+    }
+    if let Some(Token::Ident(ident)) = self.peek().cloned() {
+      match ident.as_str() {
+        "true" => {
+          self.pos += 1;
+          return Ok(Pred::True);
+        },
+        "false" => {
+          self.pos += 1;
+          return Ok(Pred::False);
+        },
+        _ => (),
+      }
+    }
+    let expr1 = self.expr()?;
+    let rel = self.rel()?;
+    let expr2 = self.expr()?;
+
+    Ok(match (expr1, rel, expr2) {
+      (Expr::Reg(processor, reg), Rel::Eq, Expr::Value(value)) => {
+        Pred::Reg(processor, reg, value)
+      },
+      (Expr::MemLoc(memloc), Rel::Eq, Expr::Value(value)) => {
+        Pred::MemLoc(memloc, value)
+      },
+      _ => Pred::Cmp(expr1, rel, expr2),
+    })
+  }
+
+  // unary ::= '~' unary | atom
+  fn unary(&mut self) -> Result<Pred, String> {
+    if self.eat("~") {
+      Ok(Pred::Not(Box::new(self.unary()?)))
+    } else {
+      self.atom()
+    }
+  }
+
+  // conj ::= unary ('/\' unary)*
+  fn conj(&mut self) -> Result<Pred, String> {
+    let mut preds = vec![self.unary()?];
+
+    while self.eat("/\\") {
+      preds.push(self.unary()?);
+    }
+    if preds.len() == 1 {
+      Ok(preds.pop().unwrap())
+    } else {
+      Ok(Pred::And(preds))
+    }
+  }
+
+  // disj ::= conj ('\/' conj)*
+  fn disj(&mut self) -> Result<Pred, String> {
+    let mut preds = vec![self.conj()?];
+
+    while self.eat("\\/") {
+      preds.push(self.conj()?);
+    }
+    if preds.len() == 1 {
+      Ok(preds.pop().unwrap())
+    } else {
+      Ok(Pred::Or(preds))
+    }
+  }
+
+  // pred ::= disj ('=>' pred)?
+  fn pred(&mut self) -> Result<Pred, String> {
+    let pred = self.disj()?;
+
+    if self.eat("=>") {
+      Ok(Pred::Implies(Box::new(pred), Box::new(self.pred()?)))
+    } else {
+      Ok(pred)
+    }
+  }
+
+  // condition ::= ('exists' | '~' 'exists' | 'forall') pred
+  fn condition(&mut self) -> Result<(Pred, PredType), String> {
+    let pred_type = if self.eat("~") {
+      match self.ident()?.as_str() {
+        "exists" => PredType::Forbidden,
+        ident => return Err(format!("expected `exists`, found `{}`", ident)),
+      }
+    } else {
+      match self.ident()?.as_str() {
+        "exists" => PredType::Allowed,
+        "forall" => PredType::Required,
+        ident => {
+          return Err(format!(
+            "expected `exists`, `~exists` or `forall`, found `{}`",
+            ident
+          ))
+        },
+      }
+    };
+    let pred = self.pred()?;
+
+    self.expect_end()?;
+    Ok((pred, pred_type))
+  }
+}
+
+enum Location {
+  Reg(Proc, Reg),
+  MemLoc(MemLoc),
+}
+
+fn parse_reg(name: &str) -> Result<Reg, String> {
+  match name.to_uppercase().as_str() {
+    "EAX" => Ok(Reg::Eax),
+    "EBX" => Ok(Reg::Ebx),
+    "ECX" => Ok(Reg::Ecx),
+    "EDX" => Ok(Reg::Edx),
+    _ => Err(format!("unknown register `{}`", name)),
+  }
+}
+
+// condition code suffix of conditional instructions, e.g. `NE` of `JNE`.
+fn parse_cond(suffix: &str) -> Option<Cond> {
+  match suffix {
+    "E" | "Z" => Some(Cond::E),
+    "NE" | "NZ" => Some(Cond::Ne),
+    "L" | "NGE" => Some(Cond::L),
+    "LE" | "NG" => Some(Cond::Le),
+    "G" | "NLE" => Some(Cond::G),
+    "GE" | "NL" => Some(Cond::Ge),
+    "B" | "C" | "NAE" => Some(Cond::B),
+    "BE" | "NA" => Some(Cond::Be),
+    "A" | "NBE" => Some(Cond::A),
+    "AE" | "NB" | "NC" => Some(Cond::Ae),
+    "S" => Some(Cond::S),
+    "NS" => Some(Cond::Ns),
+    "O" => Some(Cond::O),
+    "NO" => Some(Cond::No),
+    _ => None,
+  }
+}
+
+fn parse_proc_id(num: i32) -> Result<Proc, String> {
+  if num < 0 {
+    return Err(format!("invalid processor `{}`", num));
+  }
+  Ok(Proc(num as u32))
+}
+
+// P0, P1, ...
+fn parse_proc(name: &str) -> Result<Proc, String> {
+  match name.strip_prefix('P').unwrap_or("").parse::<u32>() {
+    Ok(num) => Ok(Proc(num)),
+    Err(_) => Err(format!("expected processor name, found `{}`", name)),
+  }
+}
+
+// Replaces (* ... *) comments with spaces, keeping line breaks so that line
+// numbers stay intact.
+fn strip_comments(text: &str) -> String {
+  let mut stripped = String::with_capacity(text.len());
+  let mut depth = 0;
+  let mut chars = text.chars().peekable();
+
+  while let Some(c) = chars.next() {
+    if c == '(' && chars.peek() == Some(&'*') {
+      chars.next();
+      depth += 1;
+      stripped.push_str("  ");
+    } else if depth > 0 && c == '*' && chars.peek() == Some(&')') {
+      chars.next();
+      depth -= 1;
+      stripped.push_str("  ");
+    } else if depth > 0 && c != '\n' {
+      stripped.push(' ');
+    } else {
+      stripped.push(c);
+    }
+  }
+  stripped
+}
+
+fn error<T>(line: usize, message: String) -> Result<T, ParseError> {
+  Err(ParseError { line, message })
+}
+
+fn is_condition(line: &str) -> bool {
+  let line = line.trim_start();
+  line.starts_with("exists") || line.starts_with("forall") ||
+    line.starts_with('~')
+}
+
+fn parse_init(
+  text: &str,
+  line: usize,
+  init: &mut State,
+) -> Result<(), ParseError> {
+  for item in text.split(';') {
+    if item.trim().is_empty() {
+      continue;
+    }
+    let mut parser = Parser::new(item).or_else(|e| error(line, e))?;
+    let location = parser.location().or_else(|e| error(line, e))?;
+    parser
+      .expect("=")
+      .and_then(|_| parser.value())
+      .and_then(|value| parser.expect_end().map(|_| value))
+      .and_then(|value| match location {
+        Location::Reg(processor, reg) => {
+          let proc_state = init.procs.get_mut(&processor).ok_or_else(|| {
+            format!("unknown processor `P{}`", processor.0)
+          })?;
+          proc_state.regs.insert(reg, value);
+          Ok(())
+        },
+        Location::MemLoc(memloc) => {
+          init.mem.insert(memloc, value);
+          Ok(())
+        },
+      })
+      .or_else(|e| error(line, e))?;
+  }
+  Ok(())
+}
+
+// First processor of a register in pred that is not one of processors.
+fn unknown_proc(pred: &Pred, processors: &[Proc]) -> Option<Proc> {
+  let unknown = |expr: &Expr| match *expr {
+    Expr::Reg(processor, _) if !processors.contains(&processor) => {
+      Some(processor)
+    },
+    _ => None,
+  };
+  match *pred {
+    Pred::Reg(processor, _, _) if !processors.contains(&processor) => {
+      Some(processor)
+    },
+    Pred::And(ref preds) | Pred::Or(ref preds) => {
+      preds.iter().filter_map(|pred| unknown_proc(pred, processors)).next()
+    },
+    Pred::Not(ref pred) => unknown_proc(pred, processors),
+    Pred::Implies(ref lhs, ref rhs) => {
+      unknown_proc(lhs, processors).or_else(|| unknown_proc(rhs, processors))
+    },
+    Pred::Cmp(ref lhs, _, ref rhs) => unknown(lhs).or_else(|| unknown(rhs)),
+    _ => None,
+  }
+}
+
+// Parses a herd-style X86 litmus test, e.g.
+//
+//   X86 SB
+//   { x=0; y=0; }
+//    P0          | P1          ;
+//    MOV [x],$1  | MOV [y],$1  ;
+//    MOV EAX,[y] | MOV EAX,[x] ;
+//   exists (0:EAX=0 /\ 1:EAX=0)
+pub fn parse(text: &str) -> Result<Litmus, ParseError> {
+  let text = strip_comments(text);
+  let lines: Vec<(usize, &str)> = text
+    .lines()
+    .enumerate()
+    .map(|(i, line)| (i + 1, line))
+    .filter(|&(_, line)| !line.trim().is_empty())
+    .collect();
+  let mut cursor = lines.into_iter().peekable();
+
+  // header, e.g. `X86 SB`.
+  let name = match cursor.next() {
+    Some((line, text)) => {
+      let words: Vec<&str> = text.split_whitespace().collect();
+      if words[0].to_uppercase() != "X86" {
+        return error(line, format!("unsupported architecture `{}`", words[0]));
+      }
+      if words.len() < 2 {
+        return error(line, "missing test name".to_string());
+      }
+      words[1..].join(" ")
+    },
+    None => return error(1, "empty litmus test".to_string()),
+  };
+
+  // skips doc strings and key=value lines up to the init block.
+  let mut init_text = String::new();
+  let mut init_line = 0;
+  while let Some((line, text)) = cursor.next() {
+    if let Some(start) = text.find('{') {
+      init_line = line;
+      let mut text = &text[start + 1..];
+      loop {
+        if let Some(end) = text.find('}') {
+          init_text.push_str(&text[..end]);
+          if !text[end + 1..].trim().is_empty() {
+            return error(line, "unexpected text after `}`".to_string());
+          }
+          break;
+        }
+        init_text.push_str(text);
+        init_text.push(' ');
+        text = match cursor.next() {
+          Some((_, text)) => text,
+          None => return error(line, "unterminated init block".to_string()),
+        };
+      }
+      break;
+    }
+  }
+  if init_line == 0 {
+    return error(1, "missing init block".to_string());
+  }
+
+  // program header, e.g. `P0 | P1 ;`.
+  let processors: Vec<Proc> = match cursor.next() {
+    Some((line, text)) => {
+      let text = text.trim();
+      let text = text.strip_suffix(';').unwrap_or(text);
+      let mut processors = vec![];
+      for column in text.split('|') {
+        processors.push(parse_proc(column.trim()).or_else(|e| error(line, e))?);
+      }
+      processors
+    },
+    None => return error(init_line, "missing program".to_string()),
+  };
+  let mut prog: BTreeMap<Proc, Vec<Inst>> = BTreeMap::new();
+  for processor in &processors {
+    prog.insert(*processor, vec![]);
+  }
+
+  let mut init = State::new(&processors);
+  parse_init(&init_text, init_line, &mut init)?;
+
+  // program rows up to the final condition.
+  let mut last_line = init_line;
+  while let Some(&(line, text)) = cursor.peek() {
+    if is_condition(text) {
+      break;
+    }
+    cursor.next();
+    last_line = line;
+    // `locations [...]` only affects herd's output, there is nothing to check.
+    if text.trim_start().starts_with("locations") {
+      continue;
+    }
+
+    let text = text.trim();
+    let text = text.strip_suffix(';').unwrap_or(text);
+    let columns: Vec<&str> = text.split('|').collect();
+    if columns.len() > processors.len() {
+      return error(line, format!(
+        "expected {} columns, found {}",
+        processors.len(),
+        columns.len()
+      ));
+    }
+    for (processor, column) in processors.iter().zip(columns) {
+      if column.trim().is_empty() {
+        continue;
+      }
+      let insts = Parser::new(column).and_then(|mut parser| parser.insts());
+      let insts = insts.or_else(|e| error(line, e))?;
+      prog.get_mut(processor).unwrap().extend(insts);
+    }
+  }
+
+  for (processor, insts) in &prog {
+    if insts.is_empty() {
+      return error(last_line, format!(
+        "processor `P{}` has no instructions",
+        processor.0
+      ));
+    }
+  }
+
+  // final condition, possibly spanning several lines.
+  let (line, first) = match cursor.next() {
+    Some(line) => line,
+    None => return error(last_line, "missing final condition".to_string()),
+  };
+  let mut condition = first.to_string();
+  for (_, text) in cursor {
+    condition.push(' ');
+    condition.push_str(text);
+  }
+  let (pred, pred_type) = Parser::new(&condition)
+    .and_then(|mut parser| parser.condition())
+    .or_else(|e| error(line, e))?;
+  if let Some(processor) = unknown_proc(&pred, &processors) {
+    return error(line, format!("unknown processor `P{}`", processor.0));
+  }
+
+  Ok(Litmus {
+    name,
+    prog: Prog(prog),
+    init,
+    pred,
+    pred_type,
+  })
+}
@@ -1,10 +1,20 @@
-use ast::{CoreProg, CoreInst};
+use ast::{CoreProg, CoreInst, AluOp};
+use config::{Config, Bound};
+use dot::StateGraph;
+use fingerprint::{collision_probability, fingerprint};
+use model::MemoryModel;
 use ast::{Proc, Value, MemLoc, Reg};
-use state::{State, ProcState, Terminal};
-use std::collections::HashSet;
+use state::{State, ProcState, Terminal, Flags};
+use std::cmp;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
 use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use trace::{Rule, Step, Trace};
 
-fn extract<'a, 'b: 'a>(
+pub fn extract<'a, 'b: 'a>(
   processor: Proc,
   prog: &'b CoreProg,
   state: &'b State,
@@ -16,34 +26,25 @@ fn extract<'a, 'b: 'a>(
   Some((proc_prog, proc_state, proc_ip))
 }
 
-fn find_latest_write(
-  storebuf: &VecDeque<(MemLoc, Value)>,
-  memloc: MemLoc,
-) -> Option<Value> {
-  for i in (0..storebuf.len()).rev() {
-    let item = storebuf.get(i).unwrap();
-    if item.0 == memloc {
-      return Some(item.1);
-    }
-  }
-  None
-}
-
-fn increase_ip(processor: Proc, prog_size: usize, state: &mut State) {
+fn jump(processor: Proc, prog_size: usize, state: &mut State, target: usize) {
   let ip: &mut Option<usize> = &mut state.procs.get_mut(&processor).unwrap().ip;
 
   if ip.is_some() {
-    let new_ip = ip.unwrap() + 1;
-
-    if new_ip < prog_size {
-      *ip = Some(new_ip);
+    if target < prog_size {
+      *ip = Some(target);
     } else {
       *ip = None;
     }
   }
 }
 
-fn mov(processor: Proc, prog: &CoreProg, state: &State) -> Option<State> {
+pub fn increase_ip(processor: Proc, prog_size: usize, state: &mut State) {
+  if let Some(ip) = state.procs[&processor].ip {
+    jump(processor, prog_size, state, ip + 1);
+  }
+}
+
+pub fn mov(processor: Proc, prog: &CoreProg, state: &State) -> Option<State> {
   let (proc_prog, proc_state, proc_ip) = extract(processor, prog, state)?;
 
   // mov value into register reg.
@@ -70,85 +71,151 @@ fn mov(processor: Proc, prog: &CoreProg, state: &State) -> Option<State> {
   }
 }
 
-fn read(processor: Proc, prog: &CoreProg, state: &State) -> Option<State> {
+pub fn alu(processor: Proc, prog: &CoreProg, state: &State) -> Option<State> {
   let (proc_prog, proc_state, proc_ip) = extract(processor, prog, state)?;
 
+  // store the computed value into register reg, and update the flags.
+  let alu_helper = |reg: Reg, value: Value, flags: Flags| -> Option<State> {
+    let mut nstate = state.clone();
+    increase_ip(processor, proc_prog.len(), &mut nstate);
+
+    let nproc_state = nstate.procs.get_mut(&processor).unwrap();
+    nproc_state.set(reg, value);
+    nproc_state.flags = flags;
+    Some(nstate)
+  };
+  let flags = proc_state.flags;
+
   match proc_prog[proc_ip] {
-    CoreInst::Read(reg, memloc) => {
-      if state.is_blocked(processor) {
-        return None;
-      }
+    CoreInst::Alu1(op, reg1, reg2) => {
+      let (value1, value2) = (proc_state.get(reg1), proc_state.get(reg2));
+      alu_helper(
+        reg1,
+        op.apply(value1, value2),
+        Flags::alu(op, value1, value2),
+      )
+    },
+    CoreInst::Alu2(op, reg, value) => {
+      let value1 = proc_state.get(reg);
+      alu_helper(reg, op.apply(value1, value), Flags::alu(op, value1, value))
+    },
+    CoreInst::Unary(op, reg) => {
+      let value = proc_state.get(reg);
+      alu_helper(reg, op.apply(value), flags.unary(op, value))
+    },
+    CoreInst::Xadd(reg1, reg2) => {
+      let mut nstate = state.clone();
+      increase_ip(processor, proc_prog.len(), &mut nstate);
 
-      let value = match find_latest_write(&proc_state.storebuf, memloc) {
-        Some(value) => value,
-        None => state.mem.get(&memloc).cloned().unwrap_or_default(),
+      let value1 = proc_state.get(reg1);
+      let value2 = proc_state.get(reg2);
+      let nproc_state = nstate.procs.get_mut(&processor).unwrap();
+      nproc_state.set(reg2, value1);
+      nproc_state.set(reg1, AluOp::Add.apply(value1, value2));
+      nproc_state.flags = Flags::add(value1, value2);
+      Some(nstate)
+    },
+    CoreInst::Cmpxchg(reg1, reg2) => {
+      let value = proc_state.get(reg1);
+      let flags = Flags::sub(proc_state.get(Reg::Eax), value);
+      if flags.zf {
+        alu_helper(reg1, proc_state.get(reg2), flags)
+      } else {
+        alu_helper(Reg::Eax, value, flags)
+      }
+    },
+    CoreInst::Cmp1(..) |
+    CoreInst::Cmp2(..) |
+    CoreInst::Test1(..) |
+    CoreInst::Test2(..) => {
+      let flags = match proc_prog[proc_ip] {
+        CoreInst::Cmp1(reg1, reg2) => {
+          Flags::sub(proc_state.get(reg1), proc_state.get(reg2))
+        },
+        CoreInst::Cmp2(reg, value) => Flags::sub(proc_state.get(reg), value),
+        CoreInst::Test1(reg1, reg2) => {
+          let value = proc_state.get(reg2);
+          Flags::logic(AluOp::And.apply(proc_state.get(reg1), value))
+        },
+        CoreInst::Test2(reg, value) => {
+          Flags::logic(AluOp::And.apply(proc_state.get(reg), value))
+        },
+        _ => unreachable!(),
       };
       let mut nstate = state.clone();
       increase_ip(processor, proc_prog.len(), &mut nstate);
 
-      nstate.procs.get_mut(&processor).unwrap().regs.insert(
-        reg,
-        value,
-      );
+      nstate.procs.get_mut(&processor).unwrap().flags = flags;
       Some(nstate)
     },
+    // setcc and cmovcc only read the flags.
+    CoreInst::Setcc(cond, reg) => {
+      alu_helper(reg, Value(flags.holds(cond) as i32), flags)
+    },
+    CoreInst::Cmovcc(cond, reg1, reg2) => {
+      let reg = if flags.holds(cond) { reg2 } else { reg1 };
+      alu_helper(reg1, proc_state.get(reg), flags)
+    },
     _ => None,
   }
 }
 
-fn write(processor: Proc, prog: &CoreProg, state: &State) -> Option<State> {
+pub fn branch(
+  processor: Proc,
+  prog: &CoreProg,
+  state: &State,
+) -> Option<State> {
   let (proc_prog, proc_state, proc_ip) = extract(processor, prog, state)?;
 
-  // write value to memloc.
-  let write_helper = |memloc: MemLoc, value: Value| -> Option<State> {
-    let mut nstate = state.clone();
-    increase_ip(processor, proc_prog.len(), &mut nstate);
-
-    nstate
-      .procs
-      .get_mut(&processor)
-      .unwrap()
-      .storebuf
-      .push_back((memloc, value));
-    Some(nstate)
+  let target = match proc_prog[proc_ip] {
+    CoreInst::Jmp(target) => target,
+    CoreInst::Jcc(cond, target) => {
+      if proc_state.flags.holds(cond) {
+        target
+      } else {
+        proc_ip + 1
+      }
+    },
+    _ => return None,
   };
+  let mut nstate = state.clone();
+  jump(processor, proc_prog.len(), &mut nstate, target);
 
-  match proc_prog[proc_ip] {
-    CoreInst::Write1(memloc, reg) => {
-      write_helper(
-        memloc,
-        proc_state.regs.get(&reg).cloned().unwrap_or_default(),
-      )
-    },
-    CoreInst::Write2(memloc, value) => write_helper(memloc, value),
-    _ => None,
-  }
+  Some(nstate)
 }
 
-fn tau(processor: Proc, _: &CoreProg, state: &State) -> Option<State> {
-  if state.is_blocked(processor) {
-    return None;
-  }
+pub fn read(processor: Proc, prog: &CoreProg, state: &State) -> Option<State> {
+  let (proc_prog, proc_state, proc_ip) = extract(processor, prog, state)?;
 
-  let mut nstate = state.clone();
-  {
-    let proc_state = nstate.procs.get_mut(&processor)?;
+  match proc_prog[proc_ip] {
+    CoreInst::Read(reg, memloc) => {
+      if state.is_blocked(processor) {
+        return None;
+      }
 
-    if let Some((memloc, value)) = proc_state.storebuf.pop_front() {
-      nstate.mem.insert(memloc, value);
-    } else {
-      return None;
-    }
+      let value = match proc_state.model.forward(memloc) {
+        Some(value) => value,
+        None => state.mem.get(&memloc).cloned().unwrap_or_default(),
+      };
+      let mut nstate = state.clone();
+      increase_ip(processor, proc_prog.len(), &mut nstate);
+
+      nstate.procs.get_mut(&processor).unwrap().regs.insert(
+        reg,
+        value,
+      );
+      Some(nstate)
+    },
+    _ => None,
   }
-  Some(nstate)
 }
 
-fn fence(processor: Proc, prog: &CoreProg, state: &State) -> Option<State> {
+pub fn fence(processor: Proc, prog: &CoreProg, state: &State) -> Option<State> {
   let (proc_prog, proc_state, proc_ip) = extract(processor, prog, state)?;
 
   match proc_prog[proc_ip] {
     CoreInst::Mfence => {
-      if proc_state.storebuf.is_empty() {
+      if proc_state.model.pending() == 0 {
         let mut nstate = state.clone();
         increase_ip(processor, proc_prog.len(), &mut nstate);
 
@@ -161,7 +228,7 @@ fn fence(processor: Proc, prog: &CoreProg, state: &State) -> Option<State> {
   }
 }
 
-fn lock(processor: Proc, prog: &CoreProg, state: &State) -> Option<State> {
+pub fn lock(processor: Proc, prog: &CoreProg, state: &State) -> Option<State> {
   let (proc_prog, _, proc_ip) = extract(processor, prog, state)?;
 
   match proc_prog[proc_ip] {
@@ -180,13 +247,17 @@ fn lock(processor: Proc, prog: &CoreProg, state: &State) -> Option<State> {
   }
 }
 
-fn unlock(processor: Proc, prog: &CoreProg, state: &State) -> Option<State> {
+pub fn unlock(
+  processor: Proc,
+  prog: &CoreProg,
+  state: &State,
+) -> Option<State> {
   let (proc_prog, proc_state, proc_ip) = extract(processor, prog, state)?;
 
   match proc_prog[proc_ip] {
     CoreInst::Unlock => {
       if state.lock_owner == Some(processor) {
-        if proc_state.storebuf.is_empty() {
+        if proc_state.model.pending() == 0 {
           let mut nstate = state.clone();
           increase_ip(processor, proc_prog.len(), &mut nstate);
 
@@ -203,44 +274,455 @@ fn unlock(processor: Proc, prog: &CoreProg, state: &State) -> Option<State> {
   }
 }
 
-pub static NEXT: [fn(Proc, &CoreProg, &State) -> Option<State>; 7] =
-  [mov, read, write, tau, fence, lock, unlock];
+// Result of exploring the whole state space of a program.
+pub struct Exploration {
+  // terminal states, each with a shortest trace reaching it.
+  pub terminals: Vec<(Terminal, Trace)>,
+  // number of distinct states visited.
+  pub states: usize,
+  // number of transitions taken, including those to visited states.
+  pub transitions: usize,
+  // bounds that cut off some transition, in ascending order.
+  pub truncated: Vec<Bound>,
+  // estimated probability of a fingerprint collision, None unless states
+  // were remembered by fingerprints.
+  pub collision: Option<f64>,
+}
 
-pub fn run(name: &'static str, prog: CoreProg, init: State) -> Vec<Terminal> {
-  let processors: Vec<Proc> = prog.0.keys().cloned().collect();
-  let mut queue: VecDeque<State> = VecDeque::new();
-  let mut hashtbl: HashSet<State> = HashSet::new();
-  let mut terminals: Vec<Terminal> = Vec::new();
+// Set of visited states with their ids, keyed by whole states or, to save
+// memory, by their fingerprints.
+enum Visited {
+  States(HashMap<State, usize>),
+  Fingerprints(HashMap<u64, usize>),
+}
 
-  queue.push_back(init.clone());
-  hashtbl.insert(init);
+impl Visited {
+  fn new(config: &Config) -> Self {
+    if config.fingerprints {
+      Visited::Fingerprints(HashMap::new())
+    } else {
+      Visited::States(HashMap::new())
+    }
+  }
+
+  fn get(&self, state: &State) -> Option<usize> {
+    match *self {
+      Visited::States(ref states) => states.get(state).cloned(),
+      Visited::Fingerprints(ref fingerprints) => {
+        fingerprints.get(&fingerprint(state)).cloned()
+      },
+    }
+  }
+
+  fn insert(&mut self, state: State, id: usize) {
+    match *self {
+      Visited::States(ref mut states) => {
+        states.insert(state, id);
+      },
+      Visited::Fingerprints(ref mut fingerprints) => {
+        fingerprints.insert(fingerprint(&state), id);
+      },
+    }
+  }
+
+  fn len(&self) -> usize {
+    match *self {
+      Visited::States(ref states) => states.len(),
+      Visited::Fingerprints(ref fingerprints) => fingerprints.len(),
+    }
+  }
+}
+
+// Collision estimate of an exploration under config.
+fn collision(
+  config: &Config,
+  states: usize,
+  transitions: usize,
+) -> Option<f64> {
+  if config.fingerprints {
+    Some(collision_probability(states, transitions))
+  } else {
+    None
+  }
+}
+
+// Checks the transition of processor by rule from state to nstate, reached
+// after depth steps, against the bounds of config, counting the iteration if
+// it is a branch backward.
+fn exceeds(
+  config: &Config,
+  processor: Proc,
+  rule: Rule,
+  state: &State,
+  nstate: &mut State,
+  depth: usize,
+) -> Option<Bound> {
+  if config.max_steps.map_or(false, |max| depth > max) {
+    return Some(Bound::Steps);
+  }
+  let nproc_state = nstate.procs.get_mut(&processor).unwrap();
+  let pending = nproc_state.model.pending();
+  if config.max_storebuf.map_or(false, |max| pending > max) {
+    return Some(Bound::StoreBuffer);
+  }
+  if let Some(max) = config.max_iterations {
+    let ip = state.procs[&processor].ip;
+    let backward = nproc_state.ip.is_some() && nproc_state.ip <= ip;
+    if rule == Rule::Branch && backward {
+      nproc_state.iterations += 1;
+      if nproc_state.iterations > max {
+        return Some(Bound::Iterations);
+      }
+    }
+  }
+  None
+}
+
+// Follows predecessor links back from state id to the initial state.
+fn backtrace(parents: &[Option<(usize, Step)>], mut id: usize) -> Trace {
+  let mut steps = vec![];
+
+  while let Some((parent, step)) = parents[id] {
+    steps.push(step);
+    id = parent;
+  }
+  steps.reverse();
+  Trace(steps)
+}
+
+// Enabled transitions from state, reached after depth steps, that stay
+// within the bounds of config, adding the bounds cutting off others to
+// truncated. written holds the locations prog writes if config.por is set.
+fn successors(
+  prog: &CoreProg,
+  processors: &[Proc],
+  config: &Config,
+  written: Option<&BTreeSet<MemLoc>>,
+  state: &State,
+  depth: usize,
+  truncated: &mut BTreeSet<Bound>,
+) -> Vec<(Step, State)> {
+  let mut successors: Vec<(Step, State)> = vec![];
+
+  for processor in processors {
+    for &(rule, next) in config.model.rules() {
+      if let Some(mut nstate) = next(*processor, prog, state) {
+        let bound =
+          exceeds(config, *processor, rule, state, &mut nstate, depth + 1);
+        if let Some(bound) = bound {
+          truncated.insert(bound);
+          continue;
+        }
+        let inst = match rule {
+          Rule::Tau => None,
+          _ => {
+            let (proc_prog, _, proc_ip) =
+              extract(*processor, prog, state).unwrap();
+            Some(proc_prog[proc_ip])
+          },
+        };
+        let step = Step {
+          processor: *processor,
+          rule,
+          inst,
+        };
+        successors.push((step, nstate));
+      }
+    }
+  }
+  // partial-order reduction: a local transition alone is a persistent set.
+  if let Some(written) = written {
+    let local = successors
+      .iter()
+      .position(|&(step, _)| is_local(config, written, step));
+    if let Some(i) = local {
+      let successor = successors.swap_remove(i);
+      successors = vec![successor];
+    }
+  }
+  successors
+}
+
+// Whether step only touches the state of its processor, either by its rule
+// or as a read of a location outside written, which always reads the
+// initial value.
+fn is_local(config: &Config, written: &BTreeSet<MemLoc>, step: Step) -> bool {
+  if config.model.is_local(step.rule) {
+    return true;
+  }
+  match step.inst {
+    Some(CoreInst::Read(_, memloc)) if step.rule == Rule::Read => {
+      !written.contains(&memloc)
+    },
+    _ => false,
+  }
+}
+
+// Locations some instruction of prog writes, None unless config.por is set
+// as only partial-order reduction needs them.
+fn written(prog: &CoreProg, config: &Config) -> Option<BTreeSet<MemLoc>> {
+  if !config.por {
+    return None;
+  }
+  let written = prog
+    .0
+    .values()
+    .flatten()
+    .filter_map(|inst| match *inst {
+      CoreInst::Write1(memloc, _) | CoreInst::Write2(memloc, _) => {
+        Some(memloc)
+      },
+      _ => None,
+    })
+    .collect();
+  Some(written)
+}
+
+// Interns every location prog accesses into init, so that the states of
+// the exploration share their table of locations, gives every processor the
+// initial state of model and finishes processors without instructions, such
+// as one holding only labels.
+pub fn prepare(prog: &CoreProg, model: &dyn MemoryModel, init: &mut State) {
+  for inst in prog.0.values().flatten() {
+    match *inst {
+      CoreInst::Read(_, memloc) |
+      CoreInst::Write1(memloc, _) |
+      CoreInst::Write2(memloc, _) => init.mem.declare(memloc),
+      _ => {},
+    }
+  }
+  for (processor, insts) in &prog.0 {
+    if let Some(proc_state) = init.procs.get_mut(processor) {
+      proc_state.model = model.init();
+      if insts.is_empty() {
+        proc_state.ip = None;
+      }
+    }
+  }
+}
+
+pub fn run(
+  name: &str,
+  prog: CoreProg,
+  init: State,
+  config: &Config,
+) -> Exploration {
+  if config.threads > 1 {
+    explore_parallel(name, prog, init, config)
+  } else {
+    explore(name, prog, init, config, None)
+  }
+}
+
+// Explores the state space as run does, also recording every state and
+// transition into graph if given.
+pub fn explore(
+  name: &str,
+  prog: CoreProg,
+  mut init: State,
+  config: &Config,
+  mut graph: Option<&mut StateGraph>,
+) -> Exploration {
+  prepare(&prog, config.model, &mut init);
+  let processors: Vec<Proc> = prog.0.keys().cloned().collect();
+  let written = written(&prog, config);
+  // states to visit, with their id and the number of steps reaching them.
+  let mut queue: VecDeque<(State, usize, usize)> = VecDeque::new();
+  let mut visited = Visited::new(config);
+  // predecessor and the step taken from it, indexed by state id.
+  let mut parents: Vec<Option<(usize, Step)>> = Vec::new();
+  let mut terminals: Vec<(Terminal, Trace)> = Vec::new();
+  let mut transitions = 0;
+  let mut truncated: BTreeSet<Bound> = BTreeSet::new();
+
+  queue.push_back((init.clone(), 0, 0));
+  if let Some(ref mut graph) = graph {
+    graph.states.push(init.clone());
+  }
+  visited.insert(init, 0);
+  parents.push(None);
 
   while !queue.is_empty() {
-    let front = queue.pop_front().unwrap();
+    let (front, id, depth) = queue.pop_front().unwrap();
 
     if front.is_final() {
-      terminals.push(front.finalize().unwrap());
+      let trace = backtrace(&parents, id);
+      terminals.push((front.finalize().unwrap(), trace));
       continue;
     }
-    for processor in &processors {
-      for next in NEXT.into_iter() {
-        if let Some(nstate) = next(*processor, &prog, &front) {
-          if hashtbl.contains(&nstate) {
+    let successors = successors(
+      &prog,
+      &processors,
+      config,
+      written.as_ref(),
+      &front,
+      depth,
+      &mut truncated,
+    );
+    for (step, nstate) in successors {
+      transitions += 1;
+      if let Some(nid) = visited.get(&nstate) {
+        if let Some(ref mut graph) = graph {
+          graph.edges.push((id, nid, step));
+        }
+        continue;
+      }
+      let nid = parents.len();
+      if let Some(ref mut graph) = graph {
+        graph.states.push(nstate.clone());
+        graph.edges.push((id, nid, step));
+      }
+      parents.push(Some((id, step)));
+      queue.push_back((nstate.clone(), nid, depth + 1));
+      visited.insert(nstate, nid);
+    }
+  }
+
+  if config.progress {
+    println!(
+      "[{}] {} states explored, {} terminal states.",
+      name,
+      visited.len(),
+      terminals.len()
+    );
+  }
+
+  Exploration {
+    terminals,
+    states: visited.len(),
+    transitions,
+    truncated: truncated.into_iter().collect(),
+    collision: collision(config, visited.len(), transitions),
+  }
+}
+
+// Number of shards of the visited set of the parallel explorer.
+const SHARDS: usize = 64;
+// Number of frontier states a worker claims at a time.
+const CHUNK: usize = 16;
+
+fn shard(state: &State) -> usize {
+  (fingerprint(state) % SHARDS as u64) as usize
+}
+
+// Work done by a worker of the parallel explorer on one level.
+#[derive(Default)]
+struct Level {
+  // final states with their id.
+  finals: Vec<(Terminal, usize)>,
+  // new states with their id, and the id of and step from their parent.
+  discovered: Vec<(State, usize, usize, Step)>,
+  transitions: usize,
+  truncated: BTreeSet<Bound>,
+}
+
+// Explores the state space breadth first like explore, one level at a time.
+// config.threads workers repeatedly claim chunks of the level until none is
+// left, sharing a visited set split into shards that are locked separately.
+// Ids grow with the level, so traces are still shortest.
+fn explore_parallel(
+  name: &str,
+  prog: CoreProg,
+  mut init: State,
+  config: &Config,
+) -> Exploration {
+  prepare(&prog, config.model, &mut init);
+  let processors: Vec<Proc> = prog.0.keys().cloned().collect();
+  let written = written(&prog, config);
+  let visited: Vec<Mutex<Visited>> =
+    (0..SHARDS).map(|_| Mutex::new(Visited::new(config))).collect();
+  let next_id = AtomicUsize::new(1);
+  let mut parents: Vec<Option<(usize, Step)>> = vec![None];
+  let mut finals: Vec<(Terminal, usize)> = vec![];
+  let mut transitions = 0;
+  let mut truncated: BTreeSet<Bound> = BTreeSet::new();
+
+  visited[shard(&init)].lock().unwrap().insert(init.clone(), 0);
+  let mut frontier: Vec<(State, usize)> = vec![(init, 0)];
+  let mut depth = 0;
+
+  while !frontier.is_empty() {
+    let cursor = AtomicUsize::new(0);
+    let work = || {
+      let mut level = Level::default();
+      loop {
+        let start = cursor.fetch_add(CHUNK, Ordering::Relaxed);
+        if start >= frontier.len() {
+          break;
+        }
+        let end = cmp::min(start + CHUNK, frontier.len());
+        for &(ref state, id) in &frontier[start..end] {
+          if state.is_final() {
+            level.finals.push((state.finalize().unwrap(), id));
             continue;
           }
-          queue.push_back(nstate.clone());
-          hashtbl.insert(nstate);
+          let successors = successors(
+            &prog,
+            &processors,
+            config,
+            written.as_ref(),
+            state,
+            depth,
+            &mut level.truncated,
+          );
+          for (step, nstate) in successors {
+            level.transitions += 1;
+            let mut shard = visited[shard(&nstate)].lock().unwrap();
+            if shard.get(&nstate).is_some() {
+              continue;
+            }
+            let nid = next_id.fetch_add(1, Ordering::Relaxed);
+            shard.insert(nstate.clone(), nid);
+            level.discovered.push((nstate, nid, id, step));
+          }
         }
       }
+      level
+    };
+    let levels: Vec<Level> = thread::scope(|scope| {
+      let workers: Vec<_> =
+        (0..config.threads).map(|_| scope.spawn(work)).collect();
+      workers
+        .into_iter()
+        .map(|worker| worker.join().unwrap())
+        .collect()
+    });
+
+    parents.resize(next_id.load(Ordering::Relaxed), None);
+    frontier = vec![];
+    for level in levels {
+      finals.extend(level.finals);
+      transitions += level.transitions;
+      truncated.extend(level.truncated);
+      for (nstate, nid, id, step) in level.discovered {
+        parents[nid] = Some((id, step));
+        frontier.push((nstate, nid));
+      }
     }
+    depth += 1;
   }
 
-  println!(
-    "[{}] {} states explored, {} terminal states.",
-    name,
-    hashtbl.len(),
-    terminals.len()
-  );
+  finals.sort_by_key(|&(_, id)| id);
+  let terminals: Vec<(Terminal, Trace)> = finals
+    .into_iter()
+    .map(|(terminal, id)| (terminal, backtrace(&parents, id)))
+    .collect();
+  let states = next_id.load(Ordering::Relaxed);
+
+  if config.progress {
+    println!(
+      "[{}] {} states explored, {} terminal states.",
+      name,
+      states,
+      terminals.len()
+    );
+  }
 
-  terminals
+  Exploration {
+    terminals,
+    states,
+    transitions,
+    truncated: truncated.into_iter().collect(),
+    collision: collision(config, states, transitions),
+  }
 }
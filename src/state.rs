@@ -1,69 +1,425 @@
-use ast::{Value, Proc, MemLoc, Reg, Pred};
+use ast::{Value, Proc, MemLoc, Reg, Pred, Expr, Cond, AluOp, UnaryOp, Prog};
+use model::{ModelState, NoState};
 use std::collections::BTreeMap;
-use std::collections::VecDeque;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Index;
+use std::iter::{FilterMap, Map, Zip};
+use std::slice;
+use std::sync::Arc;
 
-#[derive(PartialEq, Eq, Hash, Clone)]
+// Status flags, as left by the last arithmetic, logic or compare instruction.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Default, Debug)]
+pub struct Flags {
+  // zero flag.
+  pub zf: bool,
+  // sign flag.
+  pub sf: bool,
+  // carry flag, i.e. unsigned borrow.
+  pub cf: bool,
+  // overflow flag, i.e. signed overflow.
+  pub of: bool,
+}
+
+// Registers in the order of their index in Regs.
+const REGS: [Reg; 5] = [Reg::Eax, Reg::Ebx, Reg::Ecx, Reg::Edx, Reg::Internal];
+
+// Register file, indexed by register, None for registers never written.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Default, Debug)]
+pub struct Regs([Option<Value>; 5]);
+
+#[derive(Eq, Clone, Debug)]
 pub struct ProcState {
-  pub regs: BTreeMap<Reg, Value>,
-  // None if program is terminated (but may still have uncommitted writes in
-  // storebuf).
+  pub regs: Regs,
+  pub flags: Flags,
+  // None if program is terminated (but may still have writes pending under
+  // the model).
   pub ip: Option<usize>,
-  pub storebuf: VecDeque<(MemLoc, Value)>,
+  // state of the processor under the memory model, such as its store buffer.
+  pub model: Arc<dyn ModelState>,
+  // backward jumps taken, only counted when loop iterations are bounded.
+  pub iterations: usize,
 }
 
-#[derive(PartialEq, Eq, Hash, Clone)]
+// States of the processors, in ascending order. Each is shared between
+// the states it has not changed in, so a successor only copies the state of
+// the processor taking the step.
+#[derive(PartialEq, Eq, Hash, Clone, Default, Debug)]
+pub struct Procs(Vec<(Proc, Arc<ProcState>)>);
+
+// Memory, as values indexed by location. Locations are interned into a
+// table shared by every state of an exploration, and values are shared
+// between the states no write separates.
+#[derive(Clone, Default, Debug)]
+pub struct Memory {
+  // locations in ascending order.
+  memlocs: Arc<Vec<MemLoc>>,
+  // value of the location at the same index, None if never written.
+  values: Arc<Vec<Option<Value>>>,
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
 pub struct State {
-  pub procs: BTreeMap<Proc, ProcState>,
-  pub mem: BTreeMap<MemLoc, Value>,
+  pub procs: Procs,
+  pub mem: Memory,
   pub lock_owner: Option<Proc>,
 }
 
-#[derive(Clone, Default)]
+// Builder of the initial state of a program, with a processor for each of
+// its threads and every location and register 0 unless set.
+#[derive(Clone, Debug)]
+pub struct StateBuilder {
+  state: State,
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Default, Debug)]
 pub struct ProcTerminal {
   pub regs: BTreeMap<Reg, Value>,
 }
 
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
 pub struct Terminal {
   pub procs: BTreeMap<Proc, ProcTerminal>,
   pub mem: BTreeMap<MemLoc, Value>,
 }
 
+impl Flags {
+  // flags of lhs + rhs.
+  pub fn add(lhs: Value, rhs: Value) -> Self {
+    let (result, overflow) = lhs.0.overflowing_add(rhs.0);
+    Flags {
+      zf: result == 0,
+      sf: result < 0,
+      cf: (lhs.0 as u32).overflowing_add(rhs.0 as u32).1,
+      of: overflow,
+    }
+  }
+
+  // flags of lhs - rhs.
+  pub fn sub(lhs: Value, rhs: Value) -> Self {
+    let (result, overflow) = lhs.0.overflowing_sub(rhs.0);
+    Flags {
+      zf: result == 0,
+      sf: result < 0,
+      cf: (lhs.0 as u32) < (rhs.0 as u32),
+      of: overflow,
+    }
+  }
+
+  // flags of a logic operation with the given result.
+  pub fn logic(result: Value) -> Self {
+    Flags {
+      zf: result.0 == 0,
+      sf: result.0 < 0,
+      cf: false,
+      of: false,
+    }
+  }
+
+  // flags of lhs op rhs.
+  pub fn alu(op: AluOp, lhs: Value, rhs: Value) -> Self {
+    match op {
+      AluOp::Add => Flags::add(lhs, rhs),
+      AluOp::Sub => Flags::sub(lhs, rhs),
+      AluOp::And | AluOp::Or | AluOp::Xor => Flags::logic(op.apply(lhs, rhs)),
+    }
+  }
+
+  // flags of op value, inc and dec leave the carry flag unchanged.
+  pub fn unary(self, op: UnaryOp, value: Value) -> Self {
+    match op {
+      UnaryOp::Inc => Flags { cf: self.cf, ..Flags::add(value, Value(1)) },
+      UnaryOp::Dec => Flags { cf: self.cf, ..Flags::sub(value, Value(1)) },
+      UnaryOp::Neg => Flags::sub(Value(0), value),
+    }
+  }
+
+  pub fn holds(&self, cond: Cond) -> bool {
+    match cond {
+      Cond::E => self.zf,
+      Cond::Ne => !self.zf,
+      Cond::L => self.sf != self.of,
+      Cond::Le => self.zf || self.sf != self.of,
+      Cond::G => !self.zf && self.sf == self.of,
+      Cond::Ge => self.sf == self.of,
+      Cond::B => self.cf,
+      Cond::Be => self.cf || self.zf,
+      Cond::A => !self.cf && !self.zf,
+      Cond::Ae => !self.cf,
+      Cond::S => self.sf,
+      Cond::Ns => !self.sf,
+      Cond::O => self.of,
+      Cond::No => !self.of,
+    }
+  }
+}
+
+impl Regs {
+  pub fn get(&self, reg: &Reg) -> Option<&Value> {
+    self.0[*reg as usize].as_ref()
+  }
+
+  pub fn insert(&mut self, reg: Reg, value: Value) {
+    self.0[reg as usize] = Some(value);
+  }
+
+  pub fn remove(&mut self, reg: &Reg) {
+    self.0[*reg as usize] = None;
+  }
+
+  // written registers with their values, in ascending order.
+  pub fn iter(&self) -> Written<'_, Reg> {
+    REGS.iter().zip(self.0.iter()).filter_map(written)
+  }
+}
+
+// Iterator over the keys of a table with the values written to them.
+pub type Written<'a, K> = FilterMap<
+  Zip<slice::Iter<'a, K>, slice::Iter<'a, Option<Value>>>,
+  fn((&'a K, &'a Option<Value>)) -> Option<(&'a K, &'a Value)>,
+>;
+
+fn written<'a, K>(
+  (key, value): (&'a K, &'a Option<Value>),
+) -> Option<(&'a K, &'a Value)> {
+  value.as_ref().map(|value| (key, value))
+}
+
+impl<'a> IntoIterator for &'a Regs {
+  type Item = (&'a Reg, &'a Value);
+  type IntoIter = Written<'a, Reg>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter()
+  }
+}
+
+impl Procs {
+  fn position(&self, processor: &Proc) -> Option<usize> {
+    self.0.binary_search_by_key(processor, |&(p, _)| p).ok()
+  }
+
+  pub fn get(&self, processor: &Proc) -> Option<&ProcState> {
+    self.position(processor).map(|i| &*self.0[i].1)
+  }
+
+  // the state of processor, copied first if shared with another state.
+  pub fn get_mut(&mut self, processor: &Proc) -> Option<&mut ProcState> {
+    let i = self.position(processor)?;
+    Some(Arc::make_mut(&mut self.0[i].1))
+  }
+
+  pub fn insert(&mut self, processor: Proc, state: ProcState) {
+    match self.0.binary_search_by_key(&processor, |&(p, _)| p) {
+      Ok(i) => self.0[i].1 = Arc::new(state),
+      Err(i) => self.0.insert(i, (processor, Arc::new(state))),
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    self.0.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.0.is_empty()
+  }
+
+  pub fn iter(&self) -> ProcsIter<'_> {
+    self.0.iter().map(|(processor, state)| (processor, &**state))
+  }
+
+  pub fn keys(&self) -> impl Iterator<Item = &Proc> {
+    self.0.iter().map(|(processor, _)| processor)
+  }
+
+  pub fn values(&self) -> impl Iterator<Item = &ProcState> {
+    self.0.iter().map(|(_, state)| &**state)
+  }
+}
+
+impl Index<&Proc> for Procs {
+  type Output = ProcState;
+
+  fn index(&self, processor: &Proc) -> &ProcState {
+    self.get(processor).expect("no such processor")
+  }
+}
+
+pub type ProcsIter<'a> = Map<
+  slice::Iter<'a, (Proc, Arc<ProcState>)>,
+  fn(&'a (Proc, Arc<ProcState>)) -> (&'a Proc, &'a ProcState),
+>;
+
+impl<'a> IntoIterator for &'a Procs {
+  type Item = (&'a Proc, &'a ProcState);
+  type IntoIter = ProcsIter<'a>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter()
+  }
+}
+
+impl Memory {
+  pub fn get(&self, memloc: &MemLoc) -> Option<&Value> {
+    let i = self.memlocs.binary_search(memloc).ok()?;
+    self.values[i].as_ref()
+  }
+
+  // index of memloc, interning it if new.
+  fn intern(&mut self, memloc: MemLoc) -> usize {
+    match self.memlocs.binary_search(&memloc) {
+      Ok(i) => i,
+      Err(i) => {
+        Arc::make_mut(&mut self.memlocs).insert(i, memloc);
+        Arc::make_mut(&mut self.values).insert(i, None);
+        i
+      },
+    }
+  }
+
+  // interns memloc without writing it, so that states written to it later
+  // still share the table of locations.
+  pub fn declare(&mut self, memloc: MemLoc) {
+    self.intern(memloc);
+  }
+
+  pub fn insert(&mut self, memloc: MemLoc, value: Value) {
+    let i = self.intern(memloc);
+    Arc::make_mut(&mut self.values)[i] = Some(value);
+  }
+
+  // written locations with their values, in ascending order.
+  pub fn iter(&self) -> Written<'_, MemLoc> {
+    self.memlocs.iter().zip(self.values.iter()).filter_map(written)
+  }
+
+  pub fn keys(&self) -> impl Iterator<Item = &MemLoc> {
+    self.iter().map(|(memloc, _)| memloc)
+  }
+
+  pub fn to_map(&self) -> BTreeMap<MemLoc, Value> {
+    self.iter().map(|(&memloc, &value)| (memloc, value)).collect()
+  }
+}
+
+impl<'a> IntoIterator for &'a Memory {
+  type Item = (&'a MemLoc, &'a Value);
+  type IntoIter = Written<'a, MemLoc>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter()
+  }
+}
+
+// Equal if the same locations hold the same values, states of an
+// exploration sharing their table of locations.
+impl PartialEq for Memory {
+  fn eq(&self, other: &Memory) -> bool {
+    if Arc::ptr_eq(&self.memlocs, &other.memlocs) {
+      return self.values == other.values;
+    }
+    self.iter().eq(other.iter())
+  }
+}
+
+impl Eq for Memory {}
+
+// Hashes the written locations with their values, as compared by eq.
+impl Hash for Memory {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    for (memloc, value) in self.iter() {
+      memloc.hash(state);
+      value.hash(state);
+    }
+  }
+}
+
+// Written out because deriving them would compare the model states by
+// moving them out of their Arcs.
+impl PartialEq for ProcState {
+  fn eq(&self, other: &Self) -> bool {
+    self.regs == other.regs &&
+      self.flags == other.flags &&
+      self.ip == other.ip &&
+      *self.model == *other.model &&
+      self.iterations == other.iterations
+  }
+}
+
+impl Hash for ProcState {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    self.regs.hash(state);
+    self.flags.hash(state);
+    self.ip.hash(state);
+    self.model.hash(state);
+    self.iterations.hash(state);
+  }
+}
+
+impl Default for ProcState {
+  fn default() -> Self {
+    ProcState::new()
+  }
+}
+
 impl ProcState {
   pub fn new() -> Self {
     ProcState {
-      regs: BTreeMap::new(),
+      regs: Regs::default(),
+      flags: Flags::default(),
       ip: Some(0),
-      storebuf: VecDeque::new(),
+      model: Arc::new(NoState),
+      iterations: 0,
     }
   }
 
+  // value of reg, registers start out as 0.
+  pub fn get(&self, reg: Reg) -> Value {
+    self.regs.get(&reg).cloned().unwrap_or_default()
+  }
+
+  pub fn set(&mut self, reg: Reg, value: Value) {
+    self.regs.insert(reg, value);
+  }
+
   pub fn is_final(&self) -> bool {
-    self.storebuf.is_empty() && self.ip.is_none()
+    self.model.pending() == 0 && self.ip.is_none()
   }
 
   pub fn finalize(&self) -> Option<ProcTerminal> {
     if !self.is_final() {
       return None;
     }
-    Some(ProcTerminal { regs: self.regs.clone() })
+    // the internal register is scratch space of desugared instructions and
+    // not part of the outcome.
+    let mut regs = self.regs;
+    regs.remove(&Reg::Internal);
+    let regs = regs.iter().map(|(&reg, &value)| (reg, value)).collect();
+    Some(ProcTerminal { regs })
   }
 }
 
 impl State {
   pub fn new(processors: &Vec<Proc>) -> Self {
-    let mut procs = BTreeMap::new();
+    let mut procs = Procs::default();
 
     for processor in processors {
       procs.insert(*processor, ProcState::new());
     }
 
     State {
-      procs: procs,
-      mem: BTreeMap::new(),
+      procs,
+      mem: Memory::default(),
       lock_owner: None,
     }
   }
 
+  // builder of the initial state of prog.
+  pub fn builder(prog: &Prog) -> StateBuilder {
+    let processors: Vec<Proc> = prog.0.keys().cloned().collect();
+    StateBuilder { state: State::new(&processors) }
+  }
+
   pub fn is_blocked(&self, processor: Proc) -> bool {
     match self.lock_owner {
       None => false,
@@ -93,23 +449,59 @@ impl State {
       procs.insert(*processor, state.finalize().unwrap());
     }
     Some(Terminal {
-      procs: procs,
-      mem: self.mem.clone(),
+      procs,
+      mem: self.mem.to_map(),
     })
   }
 }
 
+impl StateBuilder {
+  // initial value of memloc.
+  pub fn mem(mut self, memloc: MemLoc, value: Value) -> Self {
+    self.state.mem.insert(memloc, value);
+    self
+  }
+
+  // initial value of reg on processor. Panics if processor is not one of
+  // the program, which is a bug in the caller rather than in the test.
+  pub fn reg(mut self, processor: Proc, reg: Reg, value: Value) -> Self {
+    match self.state.procs.get_mut(&processor) {
+      Some(proc_state) => proc_state.regs.insert(reg, value),
+      None => panic!("no processor {} in the program", processor),
+    }
+    self
+  }
+
+  pub fn build(self) -> State {
+    self.state
+  }
+}
+
 impl Terminal {
-  pub fn satisfy(&self, pred: &Pred) -> bool {
-    match pred {
-      &Pred::Reg(processor, reg, value) => {
-        let proc_terminal: &ProcTerminal = self.procs.get(&processor).unwrap();
-        value == proc_terminal.regs.get(&reg).cloned().unwrap_or_default()
+  pub fn eval(&self, expr: Expr) -> Value {
+    match expr {
+      // registers of processors outside the program read as 0, like unset
+      // ones, since hand-built conditions are not checked.
+      Expr::Reg(processor, reg) => self
+        .procs
+        .get(&processor)
+        .and_then(|proc_terminal| proc_terminal.regs.get(&reg))
+        .cloned()
+        .unwrap_or_default(),
+      Expr::MemLoc(memloc) => {
+        self.mem.get(&memloc).cloned().unwrap_or_default()
       },
-      &Pred::MemLoc(memloc, value) => {
-        value == self.mem.get(&memloc).cloned().unwrap_or_default()
+      Expr::Value(value) => value,
+    }
+  }
+
+  pub fn satisfy(&self, pred: &Pred) -> bool {
+    match *pred {
+      Pred::Reg(processor, reg, value) => {
+        value == self.eval(Expr::Reg(processor, reg))
       },
-      &Pred::And(ref preds) => {
+      Pred::MemLoc(memloc, value) => value == self.eval(Expr::MemLoc(memloc)),
+      Pred::And(ref preds) => {
         for pred in preds {
           if !self.satisfy(pred) {
             return false;
@@ -117,6 +509,48 @@ impl Terminal {
         }
         true
       },
+      Pred::Or(ref preds) => {
+        for pred in preds {
+          if self.satisfy(pred) {
+            return true;
+          }
+        }
+        false
+      },
+      Pred::Not(ref pred) => !self.satisfy(pred),
+      Pred::Implies(ref pred1, ref pred2) => {
+        !self.satisfy(pred1) || self.satisfy(pred2)
+      },
+      Pred::True => true,
+      Pred::False => false,
+      Pred::Cmp(expr1, rel, expr2) => {
+        rel.holds(self.eval(expr1), self.eval(expr2))
+      },
+    }
+  }
+}
+
+// Printed in herd style, e.g. `0:eax=1; 1:ebx=0; x=1;`.
+impl fmt::Display for Terminal {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let mut first = true;
+    let mut sep = |f: &mut fmt::Formatter| if first {
+      first = false;
+      Ok(())
+    } else {
+      write!(f, " ")
+    };
+
+    for (processor, proc_terminal) in &self.procs {
+      for (reg, value) in &proc_terminal.regs {
+        sep(f)?;
+        write!(f, "{}:{}={};", processor.0, reg, value)?;
+      }
+    }
+    for (memloc, value) in &self.mem {
+      sep(f)?;
+      write!(f, "{}={};", memloc, value)?;
     }
+    Ok(())
   }
 }
@@ -0,0 +1,69 @@
+use ast::{Proc, CoreInst};
+use std::fmt;
+
+// Transition rules of the x86-TSO abstract machine.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum Rule {
+  Mov,
+  Alu,
+  Read,
+  Write,
+  Tau,
+  Fence,
+  Lock,
+  Unlock,
+  Branch,
+}
+
+// A single transition taken by processor.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Step {
+  pub processor: Proc,
+  pub rule: Rule,
+  // the instruction executed, None for tau which only flushes the oldest
+  // write in the store buffer to memory.
+  pub inst: Option<CoreInst>,
+}
+
+// Sequence of transitions from the initial state to some state.
+#[derive(PartialEq, Eq, Clone, Default, Debug)]
+pub struct Trace(pub Vec<Step>);
+
+impl fmt::Display for Rule {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let name = match *self {
+      Rule::Mov => "mov",
+      Rule::Alu => "alu",
+      Rule::Read => "read",
+      Rule::Write => "write",
+      Rule::Tau => "tau",
+      Rule::Fence => "fence",
+      Rule::Lock => "lock",
+      Rule::Unlock => "unlock",
+      Rule::Branch => "branch",
+    };
+    write!(f, "{}", name)
+  }
+}
+
+impl fmt::Display for Step {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self.inst {
+      Some(inst) => write!(f, "{} {}: {}", self.processor, self.rule, inst),
+      None => write!(f, "{} {}", self.processor, self.rule),
+    }
+  }
+}
+
+// One step per line.
+impl fmt::Display for Trace {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    for (i, step) in self.0.iter().enumerate() {
+      if i > 0 {
+        writeln!(f)?;
+      }
+      write!(f, "{}", step)?;
+    }
+    Ok(())
+  }
+}
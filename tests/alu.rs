@@ -0,0 +1,86 @@
+extern crate x86_litmus;
+
+use std::collections::BTreeMap;
+use x86_litmus::ast::{Inst, Pred, PredType, Prog, AluOp, UnaryOp};
+use x86_litmus::ast::{Proc, Operand, Reg, Value, MemLoc};
+use x86_litmus::litmus;
+use x86_litmus::state::State;
+
+#[test]
+fn alu_registers() {
+  let x = Operand::MemLoc(MemLoc("x"));
+  let eax = Operand::Reg(Reg::Eax);
+  let ebx = Operand::Reg(Reg::Ebx);
+  let ecx = Operand::Reg(Reg::Ecx);
+  let three = Operand::Imm(Value(3));
+  let twelve = Operand::Imm(Value(12));
+
+  let p0 = Proc(0);
+  let i0 = vec![
+    Inst::Mov(eax, twelve),
+    Inst::Alu(AluOp::Sub, eax, three),
+    Inst::Mov(ebx, eax),
+    Inst::Alu(AluOp::And, ebx, three),
+    Inst::Alu(AluOp::Or, ebx, twelve),
+    Inst::Alu(AluOp::Xor, ebx, eax),
+    Inst::Unary(UnaryOp::Neg, eax),
+    Inst::Unary(UnaryOp::Dec, eax),
+    Inst::Mov(x, twelve),
+    Inst::Alu(AluOp::Add, ecx, x),
+    Inst::Unary(UnaryOp::Inc, ecx),
+  ];
+
+  let mut prog: BTreeMap<Proc, Vec<Inst>> = BTreeMap::new();
+  prog.insert(p0, i0);
+
+  // eax = -(12 - 3) - 1, ebx = ((9 & 3) | 12) ^ 9, ecx = 12 + 1.
+  let pred = Pred::And(vec![
+    Pred::Reg(p0, Reg::Eax, Value(-10)),
+    Pred::Reg(p0, Reg::Ebx, Value(4)),
+    Pred::Reg(p0, Reg::Ecx, Value(13)),
+  ]);
+
+  assert!(
+    litmus(
+      "alu_registers",
+      &Prog(prog),
+      State::new(&vec![p0]),
+      &pred,
+      PredType::Required,
+    ).unwrap()
+      .verdict
+  );
+}
+
+#[test]
+fn alu_lost_update() {
+  let x = Operand::MemLoc(MemLoc("x"));
+  let one = Operand::Imm(Value(1));
+
+  let p0 = Proc(0);
+  let i0 = vec![Inst::Alu(AluOp::Add, x, one)];
+
+  let p1 = Proc(1);
+  let i1 = vec![Inst::Unary(UnaryOp::Inc, x)];
+
+  let mut prog: BTreeMap<Proc, Vec<Inst>> = BTreeMap::new();
+  prog.insert(p0, i0);
+  prog.insert(p1, i1);
+
+  let result = litmus(
+    "alu_lost_update",
+    &Prog(prog),
+    State::new(&vec![p0, p1]),
+    &Pred::MemLoc(MemLoc("x"), Value(1)),
+    PredType::Allowed,
+  ).unwrap();
+
+  // both read 0 before either write reaches memory.
+  assert!(result.verdict);
+  let outcomes: Vec<String> = result
+    .outcomes
+    .iter()
+    .map(|terminal| terminal.to_string())
+    .collect();
+  assert_eq!(outcomes, vec!["x=1;", "x=2;"]);
+}
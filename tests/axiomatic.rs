@@ -0,0 +1,82 @@
+extern crate x86_litmus;
+
+mod common;
+
+use common::litmus_files;
+use x86_litmus::check::CrossCheck;
+use x86_litmus::config::Config;
+use x86_litmus::cross_check;
+use x86_litmus::parser::parse;
+
+fn check(text: &str) -> CrossCheck {
+  let test = parse(text).unwrap();
+  cross_check(&test.name, &test.prog, test.init, &Config::default()).unwrap()
+}
+
+#[test]
+fn litmus_files_agree() {
+  for text in litmus_files() {
+    let report = check(&text);
+
+    assert!(report.agrees(), "{}", report);
+    assert!(!report.axiomatic.is_empty());
+  }
+}
+
+#[test]
+fn rmw_agree() {
+  let tests = [
+    r#"
+X86 cmpxchg
+{ x=0; y=0; }
+ P0               | P1              ;
+ MOV [y],$1       | MOV EAX,$0      ;
+ MOV EBX,$1       | MOV ECX,$2      ;
+ LOCK CMPXCHG [x],EBX | CMPXCHG [x],ECX ;
+ MOV ECX,[y]      | MOV EDX,[y]     ;
+exists (x=1)
+"#,
+    r#"
+X86 xadd
+{ x=0; y=0; }
+ P0            | P1          ;
+ MOV EAX,$1    | MOV [y],$1  ;
+ XADD [x],EAX  | INC [x]     ;
+ MOV EBX,[y]   | MOV EBX,[x] ;
+exists (x=1)
+"#,
+    r#"
+X86 SB+mfence+xchg
+{ x=0; y=0; }
+ P0          | P1           ;
+ MOV [x],$1  | MOV EAX,$1   ;
+ MFENCE      | XCHG [y],EAX ;
+ MOV EBX,[y] | MOV EBX,[x]  ;
+~exists (0:EBX=0 /\ 1:EBX=0)
+"#,
+  ];
+
+  for text in tests.iter() {
+    let report = check(text);
+
+    assert!(report.agrees(), "{}", report);
+  }
+}
+
+#[test]
+fn flags_agree() {
+  let report = check(
+    r#"
+X86 setcc
+{ x=0; y=0; }
+ P0          | P1          ;
+ MOV [x],$1  | MOV EAX,[x] ;
+ MOV EAX,[y] | CMP EAX,$1  ;
+ SETE [y]    | SETE [y]    ;
+exists (y=1)
+"#,
+  );
+
+  assert!(report.agrees(), "{}", report);
+  assert_eq!(report.axiomatic.len(), 4);
+}
@@ -0,0 +1,106 @@
+extern crate x86_litmus;
+
+mod common;
+
+use common::check;
+use x86_litmus::config::{Bound, Config};
+
+const COUNTER: &str = r#"
+X86 counter
+{ }
+ P0          ;
+ MOV EAX,$0  ;
+ L0:         ;
+ INC EAX     ;
+ CMP EAX,$10 ;
+ JNE L0      ;
+forall (0:EAX=10)
+"#;
+
+#[test]
+fn loop_iterations() {
+  let config = Config { max_iterations: Some(9), ..Config::default() };
+  let result = check(COUNTER, &config);
+
+  assert!(result.is_complete());
+  assert!(result.verdict);
+
+  let config = Config { max_iterations: Some(3), ..Config::default() };
+  let result = check(COUNTER, &config);
+
+  assert_eq!(result.truncated, vec![Bound::Iterations]);
+  assert!(result.outcomes.is_empty());
+  assert!(result.to_string().contains(
+    "Incomplete, bounded by loop iterations\n",
+  ));
+}
+
+#[test]
+fn straight_line_iterations() {
+  // flushing a buffered write leaves ip where it is, without any jump.
+  let text = r#"
+X86 writes
+{ x=0; }
+ P0         ;
+ MOV [x],$1 ;
+ MOV [x],$2 ;
+ MOV [x],$3 ;
+forall (x=3)
+"#;
+  let config = Config { max_iterations: Some(1), ..Config::default() };
+  let result = check(text, &config);
+
+  assert!(result.is_complete());
+  assert!(result.verdict);
+}
+
+#[test]
+fn storebuf_depth() {
+  // P0 keeps writing while it waits, so its store buffer is unbounded.
+  let text = r#"
+X86 spin-write
+{ x=0; y=0; }
+ P0          | P1         ;
+ L0:         | MOV [y],$1 ;
+ MOV [x],$1  |            ;
+ MOV EAX,[y] |            ;
+ CMP EAX,$0  |            ;
+ JE L0       |            ;
+forall (x=1 /\ y=1 /\ 0:EAX=1)
+"#;
+  let config = Config { max_storebuf: Some(2), ..Config::default() };
+  let result = check(text, &config);
+
+  assert_eq!(result.truncated, vec![Bound::StoreBuffer]);
+  assert!(!result.is_complete());
+  assert_eq!(result.outcomes.len(), 1);
+  // the one outcome found satisfies the condition, but a missed one might
+  // not, so the test does not pass.
+  assert!(!result.verdict);
+  assert!(result.is_inconclusive());
+  assert!(result.to_string().contains("\nInconclusive\n"));
+}
+
+#[test]
+fn total_steps() {
+  let text = r#"
+X86 SB
+{ x=0; y=0; }
+ P0          | P1          ;
+ MOV [x],$1  | MOV [y],$1  ;
+ MOV EAX,[y] | MOV EBX,[x] ;
+exists (0:EAX=0 /\ 1:EBX=0)
+"#;
+  // every execution of SB takes exactly 6 steps.
+  let config = Config { max_steps: Some(6), ..Config::default() };
+  let result = check(text, &config);
+
+  assert!(result.is_complete());
+  assert_eq!(result.outcomes.len(), 4);
+
+  let config = Config { max_steps: Some(5), ..Config::default() };
+  let result = check(text, &config);
+
+  assert_eq!(result.truncated, vec![Bound::Steps]);
+  assert!(result.outcomes.is_empty());
+}
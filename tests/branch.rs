@@ -0,0 +1,139 @@
+extern crate x86_litmus;
+
+use x86_litmus::ast::{Inst, Label, Proc, Prog};
+use x86_litmus::check::CheckResult;
+use x86_litmus::desugar::{desugar, DesugarError};
+use x86_litmus::litmus;
+use x86_litmus::parser::parse;
+use std::collections::BTreeMap;
+
+fn check(text: &str) -> CheckResult {
+  let test = parse(text).unwrap();
+  litmus(&test.name, &test.prog, test.init, &test.pred, test.pred_type)
+    .unwrap()
+}
+
+#[test]
+fn spin_wait() {
+  // message passing where the reader spins until the flag is set.
+  let result = check(
+    r#"
+X86 MP-spin
+{ x=0; y=0; }
+ P0         | P1          ;
+ MOV [x],$1 | L0:         ;
+ MOV [y],$1 | MOV EAX,[y] ;
+            | CMP EAX,$1  ;
+            | JNE L0      ;
+            | MOV EBX,[x] ;
+forall (1:EBX=1)
+"#,
+  );
+
+  assert!(result.verdict);
+  assert_eq!(result.outcomes.len(), 1);
+}
+
+#[test]
+fn spin_forever() {
+  // nobody sets the flag, so no execution terminates.
+  let result = check(
+    r#"
+X86 spin
+{ x=0; }
+ P0          ;
+ L:          ;
+ MOV EAX,[x] ;
+ CMP EAX,$0  ;
+ JE L        ;
+exists (0:EAX=1)
+"#,
+  );
+
+  assert!(result.outcomes.is_empty());
+  assert!(result.is_complete());
+  assert!(!result.verdict);
+  assert!(result.to_string().contains("No\nNo terminal state\n"));
+}
+
+#[test]
+fn spinlock() {
+  let result = check(
+    r#"
+X86 spinlock
+{ l=0; c=0; }
+ P0              | P1              ;
+ L0: MOV EAX,$1  | L1: MOV EAX,$1  ;
+ XCHG [l],EAX    | XCHG [l],EAX    ;
+ TEST EAX,EAX    | TEST EAX,EAX    ;
+ JNZ L0          | JNZ L1          ;
+ MOV EBX,[c]     | MOV EBX,[c]     ;
+ ADD EBX,$1      | ADD EBX,$1      ;
+ MOV [c],EBX     | MOV [c],EBX     ;
+ MOV [l],$0      | MOV [l],$0      ;
+forall (c=2)
+"#,
+  );
+
+  assert!(result.verdict);
+}
+
+#[test]
+fn if_else() {
+  // P1 takes exactly one branch depending on the value it loads.
+  let result = check(
+    r#"
+X86 if-else
+{ x=0; }
+ P0         | P1           ;
+ MOV [x],$2 | MOV EAX,[x]  ;
+            | CMP EAX,$1   ;
+            | JL L0        ;
+            | MOV EBX,$1   ;
+            | JMP L1       ;
+            | L0:          ;
+            | MOV EBX,$2   ;
+            | L1:          ;
+forall (1:EAX=0 /\ 1:EBX=2 \/ 1:EAX=2 /\ 1:EBX=1)
+"#,
+  );
+
+  assert!(result.verdict);
+  assert_eq!(result.outcomes.len(), 2);
+}
+
+#[test]
+fn only_labels() {
+  // P0 desugars to no instructions at all and finishes at once.
+  let result = check(
+    r#"
+X86 labels
+{ x=0; }
+ P0 | P1         ;
+ L: | MOV [x],$1 ;
+forall (x=1)
+"#,
+  );
+
+  assert!(result.verdict);
+  assert_eq!(result.outcomes.len(), 1);
+}
+
+#[test]
+fn bad_labels() {
+  let p0 = Proc(0);
+  let l0 = Label("L0");
+
+  let mut prog: BTreeMap<Proc, Vec<Inst>> = BTreeMap::new();
+  prog.insert(p0, vec![Inst::Jmp(l0)]);
+  match desugar(&Prog(prog.clone())) {
+    Err(DesugarError::UnknownLabel(p, 0, _)) => assert_eq!(p, p0),
+    _ => panic!("expected an unknown label error"),
+  }
+
+  prog.insert(p0, vec![Inst::Label(l0), Inst::Mfence, Inst::Label(l0)]);
+  match desugar(&Prog(prog)) {
+    Err(DesugarError::DuplicateLabel(p, 2, _)) => assert_eq!(p, p0),
+    _ => panic!("expected a duplicate label error"),
+  }
+}
@@ -0,0 +1,65 @@
+extern crate x86_litmus;
+
+use std::collections::BTreeSet;
+use std::fs;
+use x86_litmus::catalogue::{check_all, find, CATALOGUE};
+use x86_litmus::config::Config;
+use x86_litmus::model::Sc;
+use x86_litmus::parser::parse;
+
+#[test]
+fn tso() {
+  for run in check_all(&Config::default()) {
+    assert!(run.passed(), "{}", run.entry.name);
+    assert!(run.result.is_complete());
+  }
+}
+
+#[test]
+fn sc() {
+  let config = Config { model: &Sc, ..Config::default() };
+  for run in check_all(&config) {
+    assert!(run.passed(), "{}", run.entry.name);
+  }
+}
+
+#[test]
+fn names() {
+  let names: BTreeSet<&str> =
+    CATALOGUE.iter().map(|entry| entry.name).collect();
+  assert_eq!(names.len(), CATALOGUE.len());
+  for entry in CATALOGUE {
+    assert_eq!((entry.test)().name, entry.name);
+  }
+  assert!(find("ex8").is_some());
+  assert!(find("ex11").is_none());
+}
+
+// amd3 and n7 each allow one outcome, which needs store buffers.
+#[test]
+fn amd3_and_n7() {
+  let sc = Config { model: &Sc, ..Config::default() };
+  for name in &["amd3", "n7"] {
+    let entry = find(name).unwrap();
+    let tso = entry.check(&Config::default());
+
+    assert!(tso.passed() && tso.result.verdict, "{}", name);
+    assert_eq!(tso.result.matching.len(), 1);
+    assert!(!entry.check(&sc).result.verdict, "{}", name);
+  }
+}
+
+// the catalogue agrees with the litmus files of the same tests.
+#[test]
+fn same_as_files() {
+  for file in &["sb", "iriw", "n6", "amd5", "ex9"] {
+    let path = format!("litmus/{}.litmus", file);
+    let herd = parse(&fs::read_to_string(&path).unwrap()).unwrap();
+    let test = (find(&herd.name).unwrap().test)();
+
+    assert_eq!(format!("{:?}", test.prog), format!("{:?}", herd.prog));
+    assert_eq!(test.init, herd.init);
+    assert_eq!(test.pred.to_string(), herd.pred.to_string());
+    assert_eq!(test.pred_type, herd.pred_type);
+  }
+}
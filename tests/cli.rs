@@ -0,0 +1,129 @@
+use std::env;
+use std::fs;
+use std::process::Command;
+
+fn x86_litmus(args: &[&str]) -> (i32, String) {
+  let output = Command::new(env!("CARGO_BIN_EXE_x86-litmus"))
+    .args(args)
+    .current_dir(env!("CARGO_MANIFEST_DIR"))
+    .output()
+    .unwrap();
+  (
+    output.status.code().unwrap(),
+    String::from_utf8(output.stdout).unwrap(),
+  )
+}
+
+#[test]
+fn cli_passing() {
+  let (code, stdout) = x86_litmus(&["litmus/sb.litmus", "litmus/amd5.litmus"]);
+
+  assert_eq!(code, 0);
+  assert!(stdout.contains("Test SB exists\nStates 4\n"));
+  assert!(stdout.contains("*>0:eax=0; 1:ebx=0; x=1; y=1;\n"));
+  assert!(stdout.contains("Test amd5 ~exists\nStates 3\n"));
+}
+
+#[test]
+fn cli_failing() {
+  let (code, stdout) = x86_litmus(
+    &["litmus/sb.litmus", "tests/litmus/sb-forbidden.litmus"],
+  );
+
+  assert_eq!(code, 1);
+  assert!(stdout.contains("No\nCondition ~exists (0:eax=0 /\\ 1:ebx=0)\n"));
+}
+
+#[test]
+fn cli_missing_file() {
+  let (code, _) = x86_litmus(&["litmus/missing.litmus"]);
+
+  assert_eq!(code, 2);
+}
+
+#[test]
+fn cli_bounded() {
+  let (code, stdout) = x86_litmus(&["--max-steps", "5", "litmus/sb.litmus"]);
+
+  assert_eq!(code, 1);
+  assert!(stdout.contains("Inconclusive\nIncomplete, bounded by steps\n"));
+
+  // every outcome found has c=2, but the search was cut short.
+  let (code, stdout) = x86_litmus(
+    &["--max-iterations", "1", "litmus/spinlock.litmus"],
+  );
+
+  assert_eq!(code, 1);
+  assert!(stdout.contains("States 2\n"));
+  assert!(stdout.contains("Inconclusive\nIncomplete, bounded by loop"));
+
+  let (code, _) = x86_litmus(&["--max-steps", "five", "litmus/sb.litmus"]);
+
+  assert_eq!(code, 2);
+}
+
+#[test]
+fn cli_relaxed() {
+  let (code, stdout) = x86_litmus(&["--relaxed", "litmus/sb.litmus"]);
+
+  assert_eq!(code, 0);
+  assert!(stdout.contains(
+    "Relaxed SB\nStates SC 3, x86-TSO 4\n+>0:eax=0; 1:ebx=0; x=1; y=1;\n",
+  ));
+}
+
+#[test]
+fn cli_dot() {
+  let dir = env::temp_dir().join("x86-litmus-cli-dot");
+  fs::create_dir_all(&dir).unwrap();
+  let (code, _) =
+    x86_litmus(&["--dot", dir.to_str().unwrap(), "litmus/sb.litmus"]);
+
+  assert_eq!(code, 0);
+  let dot = fs::read_to_string(dir.join("SB.dot")).unwrap();
+  assert!(dot.contains("  s0 -> s1 [label=\"P0 write\"];\n"));
+}
+
+#[test]
+fn cli_cycle() {
+  let (code, stdout) = x86_litmus(&["--cycle", "MFencedWR Fre PodWR Fre"]);
+
+  assert_eq!(code, 0);
+  assert!(stdout.contains("Test MFencedWR+Fre+PodWR+Fre exists\n"));
+  assert!(x86_litmus(&["--cycle", "PodWR Rfe"]).0 == 2);
+}
+
+#[test]
+fn cli_fuzz() {
+  let dir = env::temp_dir().join("x86-litmus-cli-fuzz");
+  fs::create_dir_all(&dir).unwrap();
+  let dir = dir.to_str().unwrap();
+  let (code, stdout) = x86_litmus(
+    &["--model", "sc", "--fuzz", "10", "--seed", "30", "--fuzz-dir", dir],
+  );
+
+  assert_eq!(code, 1);
+  assert!(stdout.contains("Discrepancy fuzz-34: axiomatic x86-TSO: "));
+  let path = format!("{}/fuzz-34.litmus", dir);
+  assert!(stdout.contains(&format!(
+    "Reproduce with x86-litmus --model sc {}\n",
+    path,
+  )));
+  assert_eq!(x86_litmus(&[&path]).0, 0);
+  assert_eq!(x86_litmus(&["--fuzz", "ten"]).0, 2);
+}
+
+#[test]
+fn cli_help() {
+  for flag in &["-h", "--help"] {
+    let (code, stdout) = x86_litmus(&[flag, "litmus/sb.litmus"]);
+
+    assert_eq!(code, 0);
+    assert!(stdout.starts_with("usage: x86-litmus "));
+  }
+
+  let (code, stdout) = x86_litmus(&[]);
+
+  assert_eq!(code, 2);
+  assert!(stdout.is_empty());
+}
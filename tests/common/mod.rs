@@ -0,0 +1,34 @@
+// Helpers shared by the integration tests, each of which uses only some.
+#![allow(dead_code)]
+
+use std::fs;
+use x86_litmus::check::CheckResult;
+use x86_litmus::config::Config;
+use x86_litmus::litmus_with_config;
+use x86_litmus::parser::parse;
+
+// Checks the litmus test in text under config.
+pub fn check(text: &str, config: &Config) -> CheckResult {
+  let test = parse(text).unwrap();
+  litmus_with_config(
+    &test.name,
+    &test.prog,
+    test.init,
+    &test.pred,
+    test.pred_type,
+    config,
+  ).unwrap()
+}
+
+// Texts of the litmus files of the repository, in the order of their names.
+pub fn litmus_files() -> Vec<String> {
+  let mut paths: Vec<_> = fs::read_dir("litmus")
+    .unwrap()
+    .map(|entry| entry.unwrap().path())
+    .collect();
+  paths.sort();
+  paths
+    .iter()
+    .map(|path| fs::read_to_string(path).unwrap())
+    .collect()
+}
@@ -0,0 +1,68 @@
+extern crate x86_litmus;
+
+use std::collections::BTreeMap;
+use x86_litmus::ast::{Inst, Pred, PredType, Prog};
+use x86_litmus::ast::{Proc, Operand, Reg, Value, MemLoc};
+use x86_litmus::desugar::DesugarError;
+use x86_litmus::litmus;
+use x86_litmus::state::State;
+
+fn check(insts: Vec<Inst>) -> Result<bool, DesugarError> {
+  let p0 = Proc(0);
+  let mut prog: BTreeMap<Proc, Vec<Inst>> = BTreeMap::new();
+  prog.insert(p0, insts);
+
+  let result = litmus(
+    "desugar",
+    &Prog(prog),
+    State::new(&vec![p0]),
+    &Pred::True,
+    PredType::Required,
+  );
+  result.map(|result| result.verdict)
+}
+
+#[test]
+fn desugar_errors() {
+  let x = Operand::MemLoc(MemLoc("x"));
+  let y = Operand::MemLoc(MemLoc("y"));
+  let eax = Operand::Reg(Reg::Eax);
+  let ebx = Operand::Reg(Reg::Ebx);
+  let internal = Operand::Reg(Reg::Internal);
+  let one = Operand::Imm(Value(1));
+  let p0 = Proc(0);
+
+  let inst = Inst::Mov(one, eax);
+  assert_eq!(
+    check(vec![Inst::Mfence, inst]),
+    Err(DesugarError::ImmediateDestination(p0, 1, inst))
+  );
+
+  let inst = Inst::Mov(x, y);
+  assert_eq!(
+    check(vec![inst]),
+    Err(DesugarError::MemoryToMemory(p0, 0, inst))
+  );
+
+  let inst = Inst::Mov(internal, one);
+  assert_eq!(
+    check(vec![Inst::Mov(x, one), inst]),
+    Err(DesugarError::InternalRegister(p0, 1, inst))
+  );
+
+  let inst = Inst::Xchg(x, one);
+  assert_eq!(
+    check(vec![inst]),
+    Err(DesugarError::ImmediateDestination(p0, 0, inst))
+  );
+
+  let inst = Inst::Xchg(eax, ebx);
+  let error = check(vec![inst]).unwrap_err();
+  assert_eq!(error, DesugarError::Unsupported(p0, 0, inst));
+  assert_eq!(
+    error.to_string(),
+    "P0, instruction 0 `xchg eax, ebx`: unsupported operands"
+  );
+
+  assert_eq!(check(vec![Inst::Xchg(x, eax)]), Ok(true));
+}
@@ -0,0 +1,110 @@
+extern crate x86_litmus;
+
+use x86_litmus::config::Config;
+use x86_litmus::diy::{cycles, generate, parse_cycle, Edge, Sep};
+use x86_litmus::graph::Dir;
+use x86_litmus::model::{MemoryModel, Sc, Tso};
+use x86_litmus::parser::Litmus;
+use x86_litmus::{cross_check, litmus_with_config};
+
+fn allowed(test: Litmus, model: &'static dyn MemoryModel) -> bool {
+  let config = Config { model, ..Config::default() };
+  litmus_with_config(
+    &test.name,
+    &test.prog,
+    test.init,
+    &test.pred,
+    test.pred_type,
+    &config,
+  ).unwrap()
+    .verdict
+}
+
+fn generated(cycle: &str) -> Litmus {
+  generate(&parse_cycle(cycle).unwrap()).unwrap()
+}
+
+#[test]
+fn sb() {
+  let test = generated("PodWR Fre PodWR Fre");
+
+  assert_eq!(test.name, "PodWR+Fre+PodWR+Fre");
+  assert_eq!(test.pred.to_string(), "(0:eax=0 /\\ 1:eax=0)");
+  assert!(allowed(test, &Tso));
+  assert!(!allowed(generated("PodWR Fre PodWR Fre"), &Sc));
+}
+
+#[test]
+fn forbidden() {
+  for cycle in &[
+    "Rfe PodRR Fre Rfe PodRR Fre",
+    "PodWW Rfe PodRR Fre",
+    "PodWW Wse PodWW Wse",
+    "MFencedWR Fre MFencedWR Fre",
+    "XchgdWR Fre XchgdWR Fre",
+    "MFencedWR Fre XchgdWR Fre",
+  ] {
+    assert!(!allowed(generated(cycle), &Tso), "{}", cycle);
+  }
+}
+
+#[test]
+fn names() {
+  let cycle = "PosWR Rfi MFencedRW Wse XchgdWW Fri Wsi Fre";
+  let edges = parse_cycle(cycle).unwrap();
+  let names: Vec<String> = edges.iter().map(|edge| edge.to_string()).collect();
+
+  assert_eq!(names.join(" "), cycle);
+  assert_eq!(edges[0], Edge::Po {
+    sep: Sep::Plain,
+    same: true,
+    src: Dir::Write,
+    dst: Dir::Read,
+  });
+  assert!(parse_cycle("PodWX").is_err());
+  assert!(parse_cycle("XchgdRW").is_err());
+}
+
+#[test]
+fn invalid() {
+  let error = |cycle: &str| generate(&parse_cycle(cycle).unwrap()).err();
+
+  assert!(error("PodWR Rfe").unwrap().contains("ends with a R"));
+  assert!(error("PodWR Fri").unwrap().contains("no external edge"));
+  assert!(error("PodWW Wse PosWW Wse").unwrap().contains("same location"));
+  assert!(error("").is_some());
+}
+
+// every cycle is forbidden under SC, and under x86-TSO exactly those with a
+// plain write-to-read program order are allowed.
+#[test]
+fn sweep() {
+  let po = Edge::po(true, true);
+  for threads in 2..4 {
+    let cycles = cycles(threads, &po);
+    assert!(!cycles.is_empty());
+    for cycle in cycles {
+      let relaxed = cycle.contains(&Edge::Po {
+        sep: Sep::Plain,
+        same: false,
+        src: Dir::Write,
+        dst: Dir::Read,
+      });
+      let test = generate(&cycle).unwrap();
+      let name = test.name.clone();
+      assert!(!allowed(generate(&cycle).unwrap(), &Sc), "{}", name);
+      assert_eq!(allowed(test, &Tso), relaxed, "{}", name);
+    }
+  }
+}
+
+#[test]
+fn cross_checked() {
+  let po = Edge::po(true, true);
+  for cycle in cycles(2, &po) {
+    let test = generate(&cycle).unwrap();
+    let report =
+      cross_check(&test.name, &test.prog, test.init, &Config::default());
+    assert!(report.unwrap().agrees(), "{}", test.name);
+  }
+}
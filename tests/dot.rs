@@ -0,0 +1,36 @@
+extern crate x86_litmus;
+
+use x86_litmus::config::Config;
+use x86_litmus::parser::parse;
+use x86_litmus::state_graph;
+
+#[test]
+fn sb_state_graph() {
+  let test = parse(
+    r#"
+X86 SB
+{ x=0; y=0; }
+ P0          | P1          ;
+ MOV [x],$1  | MOV [y],$1  ;
+ MOV EAX,[y] | MOV EBX,[x] ;
+exists (0:EAX=0 /\ 1:EBX=0)
+"#,
+  ).unwrap();
+  let graph = state_graph(&test.name, &test.prog, test.init, &Config::default())
+    .unwrap();
+
+  assert_eq!(graph.states.len(), 34);
+  assert_eq!(graph.edges.len(), 58);
+  assert_eq!(graph.states.iter().filter(|state| state.is_final()).count(), 4);
+
+  let dot = graph.to_dot(&test.name);
+  assert!(dot.starts_with("digraph \"SB\" {\n"));
+  assert!(dot.contains(
+    "  s0 [label=\"P0 ip=0 []\\nP1 ip=0 []\\nx=0 y=0\", style=bold];\n",
+  ));
+  assert!(dot.contains(
+    "  s1 [label=\"P0 ip=1 [x=1]\\nP1 ip=0 []\\nx=0 y=0\"];\n",
+  ));
+  assert!(dot.contains("  s0 -> s1 [label=\"P0 write\"];\n"));
+  assert_eq!(dot.matches("peripheries=2").count(), 4);
+}
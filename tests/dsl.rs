@@ -0,0 +1,70 @@
+extern crate x86_litmus;
+
+use std::fs;
+use x86_litmus::ast::{MemLoc, PredType, Proc, Reg, Value};
+use x86_litmus::litmus;
+use x86_litmus::parser::{parse, Litmus};
+
+fn check(test: Litmus) -> bool {
+  litmus(&test.name, &test.prog, test.init, &test.pred, test.pred_type)
+    .unwrap()
+    .verdict
+}
+
+#[test]
+fn same_as_herd() {
+  let test = litmus! {
+    SB { x = 0; y = 0; }
+    P0: mov [x], $1; mov eax, [y]
+    | P1: mov [y], $1; mov ebx, [x]
+    exists (0:eax = 0 && 1:ebx = 0)
+  };
+  let herd = parse(&fs::read_to_string("litmus/sb.litmus").unwrap()).unwrap();
+
+  assert_eq!(test.name, herd.name);
+  assert_eq!(format!("{:?}", test.prog), format!("{:?}", herd.prog));
+  assert_eq!(test.init, herd.init);
+  assert_eq!(test.pred.to_string(), herd.pred.to_string());
+  assert_eq!(test.pred_type, herd.pred_type);
+  assert!(check(test));
+}
+
+#[test]
+fn locked_loop() {
+  let test = litmus! {
+    spinlock
+    P0: L0: mov eax, $1; xchg [l], eax; test eax, eax; jnz L0;
+        mov ebx, [c]; add ebx, $1; mov [c], ebx; mov [l], $0
+    | P1: L1: mov eax, $1; xchg [l], eax; test eax, eax; jnz L1;
+        mov ebx, [c]; add ebx, $1; mov [c], ebx; mov [l], $0
+    forall (c = 2)
+  };
+
+  assert_eq!(test.pred_type, PredType::Required);
+  assert!(check(test));
+}
+
+#[test]
+fn initial_values() {
+  let test = litmus! {
+    "init values" { x = -1; 1:eax = 2; }
+    P0: mov ebx, [x]
+    | P1: mov [x], eax
+    ~exists (0:ebx = 0 || x = -1)
+  };
+
+  assert_eq!(test.name, "init values");
+  assert_eq!(test.init.mem.get(&MemLoc("x")), Some(&Value(-1)));
+  assert_eq!(test.init.procs[&Proc(1)].get(Reg::Eax), Value(2));
+  assert_eq!(test.pred_type, PredType::Forbidden);
+  assert!(check(test));
+}
+
+#[test]
+#[should_panic(expected = "missing final condition")]
+fn missing_condition() {
+  litmus! {
+    MP
+    P0: mov [x], $1
+  };
+}
@@ -36,5 +36,7 @@ fn ex9() {
     Pred::Reg(p1, Reg::Ecx, Value(0)),
   ]);
 
-  assert!(litmus("ex10", &Prog(prog), init, &pred, PredType::Forbidden));
+  assert!(
+    litmus("ex10", &Prog(prog), init, &pred, PredType::Forbidden).unwrap().verdict
+  );
 }
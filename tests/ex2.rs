@@ -35,5 +35,5 @@ fn ex2() {
     State::new(&vec![p0, p1]),
     &pred,
     PredType::Forbidden,
-  ));
+  ).unwrap().verdict);
 }
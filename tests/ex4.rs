@@ -26,5 +26,5 @@ fn ex4() {
     State::new(&vec![p0]),
     &pred,
     PredType::Required,
-  ));
+  ).unwrap().verdict);
 }
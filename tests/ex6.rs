@@ -41,5 +41,5 @@ fn ex2() {
     State::new(&vec![p0, p1, p2]),
     &pred,
     PredType::Forbidden,
-  ));
+  ).unwrap().verdict);
 }
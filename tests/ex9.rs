@@ -40,5 +40,7 @@ fn ex9() {
     Pred::Reg(p1, Reg::Edx, Value(0)),
   ]);
 
-  assert!(litmus("ex9", &Prog(prog), init, &pred, PredType::Forbidden));
+  assert!(
+    litmus("ex9", &Prog(prog), init, &pred, PredType::Forbidden).unwrap().verdict
+  );
 }
@@ -0,0 +1,84 @@
+extern crate x86_litmus;
+
+mod common;
+
+use common::{check, litmus_files};
+use std::fs;
+use x86_litmus::config::Config;
+use x86_litmus::fingerprint::collision_probability;
+
+#[test]
+fn same_as_states() {
+  for text in litmus_files() {
+    for &threads in &[1, 4] {
+      let config = Config { threads, ..Config::default() };
+      let states = check(&text, &config);
+      let fingerprints =
+        check(&text, &Config { fingerprints: true, ..config });
+
+      let name = &states.name;
+      assert_eq!(states.outcomes, fingerprints.outcomes, "{}", name);
+      assert_eq!(states.states, fingerprints.states);
+      assert_eq!(states.collision, None);
+      assert!(fingerprints.collision.unwrap() < 1e-12);
+    }
+  }
+}
+
+#[test]
+fn iriw() {
+  let text = fs::read_to_string("litmus/iriw.litmus").unwrap();
+  let config = Config { fingerprints: true, ..Config::default() };
+  let result = check(&text, &config);
+
+  assert_eq!((result.states, result.transitions), (164, 330));
+  assert_eq!(result.collision, Some(collision_probability(164, 330)));
+}
+
+#[test]
+fn probability() {
+  assert_eq!(collision_probability(1, 0), 0.0);
+  assert_eq!(collision_probability(1 << 32, (1 << 33) - 1), 1.0);
+  assert!(collision_probability(1000, 5000) < 1e-12);
+}
+
+#[test]
+fn report() {
+  let text = fs::read_to_string("litmus/sb.litmus").unwrap();
+  let config = Config { fingerprints: true, ..Config::default() };
+  let result = check(&text, &config).to_string();
+
+  assert!(result.lines().last().unwrap().starts_with(
+    "Fingerprint collision probability "
+  ));
+}
+
+// x and y start out unwritten and the branches end in the same registers
+// and flags, so only the location written tells them apart.
+#[test]
+fn uninitialised() {
+  let text = r#"
+X86 branch
+{ c=0; }
+ P0           | P1         ;
+ MOV EAX,[c]  | MOV [c],$1 ;
+ CMP EAX,$0   |            ;
+ JE L         |            ;
+ MOV [x],$1   |            ;
+ JMP E        |            ;
+ L:           |            ;
+ MOV [y],$1   |            ;
+ E:           |            ;
+ MOV EAX,$0   |            ;
+ CMP EAX,$0   |            ;
+exists (x=1)
+"#;
+  let states = check(text, &Config::default());
+  let config = Config { fingerprints: true, ..Config::default() };
+  let fingerprints = check(text, &config);
+
+  assert_eq!(states.outcomes.len(), 2);
+  assert_eq!(states.outcomes, fingerprints.outcomes);
+  assert_eq!(states.states, fingerprints.states);
+  assert!(fingerprints.verdict);
+}
@@ -0,0 +1,93 @@
+extern crate x86_litmus;
+
+use x86_litmus::check::CheckResult;
+use x86_litmus::litmus;
+use x86_litmus::parser::parse;
+
+fn check(text: &str) -> CheckResult {
+  let test = parse(text).unwrap();
+  litmus(&test.name, &test.prog, test.init, &test.pred, test.pred_type)
+    .unwrap()
+}
+
+#[test]
+fn arithmetic_flags() {
+  let result = check(
+    r#"
+X86 flags
+{ x=0; }
+ P0            ;
+ MOV EAX,$-1   ;
+ ADD EAX,$1    ;
+ SETZ EBX      ;
+ SETC ECX      ;
+ INC EAX       ;
+ SETC EDX      ;
+ SETNS [x]     ;
+forall (0:EBX=1 /\ 0:ECX=1 /\ 0:EDX=1 /\ x=1)
+"#,
+  );
+
+  assert!(result.verdict);
+}
+
+#[test]
+fn signed_overflow() {
+  let result = check(
+    r#"
+X86 overflow
+{ }
+ P0                 ;
+ MOV EAX,$2147483647 ;
+ ADD EAX,$1         ;
+ SETO EBX           ;
+ SETL ECX           ;
+ SUB EAX,$1         ;
+ SETO EDX           ;
+forall (0:EBX=1 /\ 0:ECX=0 /\ 0:EDX=1)
+"#,
+  );
+
+  assert!(result.verdict);
+}
+
+#[test]
+fn cmov_from_memory() {
+  let result = check(
+    r#"
+X86 MP-cmov
+{ x=0; y=0; }
+ P0         | P1            ;
+ MOV [x],$1 | MOV EAX,[y]   ;
+ MOV [y],$1 | TEST EAX,EAX  ;
+            | MOV EBX,$-1   ;
+            | CMOVNZ EBX,[x] ;
+forall (1:EAX=0 /\ 1:EBX=-1 \/ 1:EAX=1 /\ 1:EBX=1)
+"#,
+  );
+
+  assert!(result.verdict);
+  assert_eq!(result.outcomes.len(), 2);
+}
+
+#[test]
+fn flags_distinguish_states() {
+  // both paths reach a state that differs only in the flags before the jump.
+  let result = check(
+    r#"
+X86 flags-state
+{ x=0; }
+ P0          | P1         ;
+ MOV EAX,[x] | MOV [x],$1 ;
+ CMP EAX,$1  |            ;
+ MOV EAX,$0  |            ;
+ JE L0       |            ;
+ MOV EBX,$1  |            ;
+ L0:         |            ;
+exists (0:EBX=1)
+"#,
+  );
+
+  assert!(result.verdict);
+  assert_eq!(result.outcomes.len(), 2);
+}
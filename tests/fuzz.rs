@@ -0,0 +1,101 @@
+extern crate x86_litmus;
+
+use x86_litmus::ast::{MemLoc, Prog, Value};
+use x86_litmus::config::Config;
+use x86_litmus::fuzz::{compare, fuzz, random_prog, Check, Discrepancy};
+use x86_litmus::fuzz::{Limits, Rng};
+use x86_litmus::litmus_with_config;
+use x86_litmus::model::{MemoryModel, Sc, Tso};
+use x86_litmus::parser::{parse, Litmus};
+use x86_litmus::state::Terminal;
+use std::collections::BTreeMap;
+
+fn passes(test: &Litmus, model: &'static dyn MemoryModel) -> bool {
+  let config = Config { model, ..Config::default() };
+  litmus_with_config(
+    &test.name,
+    &test.prog,
+    test.init.clone(),
+    &test.pred,
+    test.pred_type,
+    &config,
+  ).unwrap()
+    .verdict
+}
+
+#[test]
+fn tso() {
+  let found = fuzz(0, 20, &Limits::default(), &Config::default());
+
+  assert!(found.is_empty());
+}
+
+#[test]
+fn random() {
+  let limits = Limits { processors: 4, insts: 2, memlocs: 3 };
+  for seed in 0..20 {
+    let prog = random_prog(&mut Rng::new(seed), &limits);
+    let again = random_prog(&mut Rng::new(seed), &limits);
+
+    assert_eq!(format!("{:?}", prog), format!("{:?}", again));
+    assert!((2..=4).contains(&prog.0.len()));
+    assert!(prog.0.values().all(|insts| (1..=2).contains(&insts.len())));
+  }
+}
+
+// SC stands in for an x86-TSO model that forgot its store buffers.
+#[test]
+fn minimized() {
+  let config = Config { model: &Sc, ..Config::default() };
+  let found = fuzz(30, 10, &Limits::default(), &config);
+
+  assert_eq!(found.len(), 1);
+  let counterexample = &found[0];
+  assert_eq!(counterexample.seed, 34);
+  assert_eq!(counterexample.discrepancy.check, Check::Axiomatic);
+  assert!(counterexample.discrepancy.expected);
+
+  // the test still shows the discrepancy, and no longer without any one of
+  // its instructions.
+  let prog = &counterexample.test.prog;
+  assert_eq!(
+    compare(prog, &config).map(|discrepancy| discrepancy.check),
+    Some(Check::Axiomatic),
+  );
+  for (processor, insts) in &prog.0 {
+    for index in 0..insts.len() {
+      let mut smaller = prog.0.clone();
+      smaller.get_mut(processor).unwrap().remove(index);
+      smaller.retain(|_, insts| !insts.is_empty());
+      assert!(compare(&Prog(smaller), &config).is_none());
+    }
+  }
+}
+
+#[test]
+fn reproducible() {
+  let config = Config { model: &Sc, ..Config::default() };
+  let found = fuzz(34, 1, &Limits::default(), &config);
+  let test = parse(&found[0].test.to_string()).unwrap();
+
+  assert_eq!(test.name, "fuzz-34");
+  assert!(passes(&test, &Tso));
+  assert!(!passes(&test, &Sc));
+}
+
+#[test]
+fn options() {
+  let mut mem = BTreeMap::new();
+  mem.insert(MemLoc("x"), Value(1));
+  let outcome = Terminal { procs: BTreeMap::new(), mem };
+  let discrepancy =
+    Discrepancy { check: Check::Por, outcome, expected: true };
+
+  assert_eq!(Check::Axiomatic.flag(), None);
+  assert_eq!(Check::Threads.flag(), Some("--threads 2"));
+  assert_eq!(
+    discrepancy.to_string(),
+    "partial-order reduction: x=1; reached by a plain exploration but not \
+     with --por",
+  );
+}
@@ -0,0 +1,96 @@
+extern crate x86_litmus;
+
+use x86_litmus::ast::{MemLoc, Proc, Value};
+use x86_litmus::config::Config;
+use x86_litmus::graph::{Dir, Graph};
+use x86_litmus::graphs;
+use x86_litmus::parser::parse;
+use x86_litmus::state::Terminal;
+
+fn graphs_of(text: &str) -> Vec<(Terminal, Graph)> {
+  let test = parse(text).unwrap();
+  graphs(&test.name, &test.prog, test.init, &Config::default()).unwrap()
+}
+
+#[test]
+fn sb_graph() {
+  let graphs = graphs_of(
+    r#"
+X86 SB
+{ x=0; y=0; }
+ P0          | P1          ;
+ MOV [x],$1  | MOV [y],$1  ;
+ MOV EAX,[y] | MOV EBX,[x] ;
+exists (0:EAX=0 /\ 1:EBX=0)
+"#,
+  );
+
+  assert_eq!(graphs.len(), 4);
+  // both reads see the initial writes, which precede the other processor's
+  // write in coherence.
+  let (ref terminal, ref graph) = graphs[0];
+  assert_eq!(terminal.to_string(), "0:eax=0; 1:ebx=0; x=1; y=1;");
+  assert_eq!(graph.events.len(), 6);
+  assert_eq!(graph.po, vec![(2, 3), (4, 5)]);
+  assert_eq!(graph.rf, vec![(1, 3), (0, 5)]);
+  assert_eq!(graph.co, vec![(0, 2), (1, 4)]);
+  assert_eq!(graph.fr, vec![(3, 4), (5, 2)]);
+}
+
+#[test]
+fn forwarding_graph() {
+  // P0 reads its own write from its store buffer, before P1's write to x
+  // which then wins coherence.
+  let graphs = graphs_of(
+    r#"
+X86 n6
+{ x=0; y=0; }
+ P0          | P1         ;
+ MOV [x],$1  | MOV [y],$2 ;
+ MOV EAX,[x] | MOV [x],$2 ;
+ MOV EBX,[y] |            ;
+exists (0:EAX=1 /\ 0:EBX=0 /\ x=1)
+"#,
+  );
+  let outcome = "0:eax=1; 0:ebx=0; x=1; y=2;";
+  let graph = &graphs
+    .iter()
+    .find(|&(terminal, _)| terminal.to_string() == outcome)
+    .unwrap()
+    .1;
+  let id = |processor: u32, dir: Dir, memloc: &'static str| {
+    graph
+      .events
+      .iter()
+      .position(|event| {
+        event.processor == Some(Proc(processor)) && event.dir == dir &&
+          event.memloc == MemLoc(memloc)
+      })
+      .unwrap()
+  };
+
+  let (w0, r0) = (id(0, Dir::Write, "x"), id(0, Dir::Read, "x"));
+  assert_eq!(graph.events[r0].value, Value(1));
+  assert!(graph.rf.contains(&(w0, r0)));
+  assert!(graph.co.contains(&(id(1, Dir::Write, "x"), w0)));
+}
+
+#[test]
+fn locked_graph() {
+  let graphs = graphs_of(
+    r#"
+X86 lock-inc
+{ x=0; }
+ P0           | P1           ;
+ LOCK INC [x] | LOCK INC [x] ;
+forall (x=2)
+"#,
+  );
+
+  assert_eq!(graphs.len(), 1);
+  let graph = &graphs[0].1;
+  assert!(graph.events[1..].iter().all(|event| event.locked));
+  assert_eq!(graph.co.len(), 2);
+  assert_eq!(graph.fr.len(), 2);
+  assert!(graph.to_string().contains("b: P0 R* x=0\n"));
+}
@@ -0,0 +1,114 @@
+extern crate x86_litmus;
+
+use std::collections::BTreeMap;
+use x86_litmus::ast::{Inst, Pred, PredType, Prog};
+use x86_litmus::ast::{Proc, Operand, Reg, Value, MemLoc};
+use x86_litmus::litmus;
+use x86_litmus::state::State;
+
+// Message passing where the flag starts out set, so the reader may see it
+// before the data is written.
+#[test]
+fn initial_memory() {
+  let data = Operand::MemLoc(MemLoc("data"));
+  let flag = Operand::MemLoc(MemLoc("flag"));
+  let eax = Operand::Reg(Reg::Eax);
+  let ebx = Operand::Reg(Reg::Ebx);
+
+  let p0 = Proc(0);
+  let i0 = vec![
+    Inst::Mov(data, Operand::Imm(Value(42))),
+    Inst::Mov(flag, Operand::Imm(Value(2))),
+  ];
+
+  let p1 = Proc(1);
+  let i1 = vec![Inst::Mov(eax, flag), Inst::Mov(ebx, data)];
+
+  let mut prog: BTreeMap<Proc, Vec<Inst>> = BTreeMap::new();
+  prog.insert(p0, i0);
+  prog.insert(p1, i1);
+  let prog = Prog(prog);
+
+  let init = State::builder(&prog)
+    .mem(MemLoc("flag"), Value(1))
+    .mem(MemLoc("data"), Value(7))
+    .build();
+  let pred = Pred::And(vec![
+    Pred::Reg(p1, Reg::Eax, Value(1)),
+    Pred::Reg(p1, Reg::Ebx, Value(7)),
+  ]);
+
+  assert!(
+    litmus("mp-init", &prog, init, &pred, PredType::Allowed).unwrap().verdict
+  );
+}
+
+// A register holding a value before the program starts, written to memory.
+#[test]
+fn initial_register() {
+  let x = Operand::MemLoc(MemLoc("x"));
+  let eax = Operand::Reg(Reg::Eax);
+
+  let p0 = Proc(0);
+  let mut prog: BTreeMap<Proc, Vec<Inst>> = BTreeMap::new();
+  prog.insert(p0, vec![Inst::Mov(x, eax)]);
+  let prog = Prog(prog);
+
+  let init = State::builder(&prog).reg(p0, Reg::Eax, Value(5)).build();
+  let pred = Pred::MemLoc(MemLoc("x"), Value(5));
+
+  assert!(
+    litmus("reg-init", &prog, init, &pred, PredType::Required).unwrap().verdict
+  );
+}
+
+// Intel SDM 8.2.3.9 with the xchg operands set on both processors.
+#[test]
+fn registers_of_each_processor() {
+  let x = Operand::MemLoc(MemLoc("x"));
+  let y = Operand::MemLoc(MemLoc("y"));
+  let eax = Operand::Reg(Reg::Eax);
+  let ebx = Operand::Reg(Reg::Ebx);
+  let ecx = Operand::Reg(Reg::Ecx);
+  let edx = Operand::Reg(Reg::Edx);
+
+  let (p0, p1) = (Proc(0), Proc(1));
+  let mut prog: BTreeMap<Proc, Vec<Inst>> = BTreeMap::new();
+  prog.insert(p0, vec![Inst::Xchg(x, eax), Inst::Mov(ebx, y)]);
+  prog.insert(p1, vec![Inst::Xchg(y, ecx), Inst::Mov(edx, x)]);
+  let prog = Prog(prog);
+
+  let init = State::builder(&prog)
+    .reg(p0, Reg::Eax, Value(1))
+    .reg(p1, Reg::Ecx, Value(1))
+    .build();
+  let pred = Pred::And(vec![
+    Pred::Reg(p0, Reg::Ebx, Value(0)),
+    Pred::Reg(p1, Reg::Edx, Value(0)),
+  ]);
+
+  assert!(
+    litmus("ex9", &prog, init, &pred, PredType::Forbidden).unwrap().verdict
+  );
+}
+
+#[test]
+fn processors_from_prog() {
+  let mut prog: BTreeMap<Proc, Vec<Inst>> = BTreeMap::new();
+  prog.insert(Proc(0), vec![]);
+  prog.insert(Proc(2), vec![]);
+
+  let init = State::builder(&Prog(prog)).build();
+  let processors: Vec<Proc> = init.procs.keys().cloned().collect();
+  assert_eq!(processors, vec![Proc(0), Proc(2)]);
+  assert_eq!(init, State::new(&vec![Proc(0), Proc(2)]));
+}
+
+#[test]
+#[should_panic(expected = "no processor P1 in the program")]
+fn unknown_processor() {
+  let mut prog: BTreeMap<Proc, Vec<Inst>> = BTreeMap::new();
+  prog.insert(Proc(0), vec![]);
+
+  State::builder(&Prog(prog)).reg(Proc(1), Reg::Eax, Value(1));
+}
@@ -47,5 +47,5 @@ fn iriw() {
     State::new(&vec![p0, p1, p2, p3]),
     &pred,
     PredType::Forbidden,
-  ));
+  ).unwrap().verdict);
 }
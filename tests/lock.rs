@@ -0,0 +1,136 @@
+extern crate x86_litmus;
+
+use std::collections::BTreeMap;
+use x86_litmus::ast::{Inst, Pred, PredType, Prog, AluOp, UnaryOp};
+use x86_litmus::ast::{Proc, Operand, Reg, Value, MemLoc};
+use x86_litmus::litmus;
+use x86_litmus::parser::parse;
+use x86_litmus::state::State;
+
+#[test]
+fn lock_inc() {
+  let x = Operand::MemLoc(MemLoc("x"));
+  let one = Operand::Imm(Value(1));
+
+  let p0 = Proc(0);
+  let i0 = vec![Inst::LockAlu(AluOp::Add, x, one)];
+
+  let p1 = Proc(1);
+  let i1 = vec![Inst::LockUnary(UnaryOp::Inc, x)];
+
+  let mut prog: BTreeMap<Proc, Vec<Inst>> = BTreeMap::new();
+  prog.insert(p0, i0);
+  prog.insert(p1, i1);
+
+  assert!(
+    litmus(
+      "lock_inc",
+      &Prog(prog),
+      State::new(&vec![p0, p1]),
+      &Pred::MemLoc(MemLoc("x"), Value(2)),
+      PredType::Required,
+    ).unwrap()
+      .verdict
+  );
+}
+
+#[test]
+fn lock_xadd() {
+  let x = Operand::MemLoc(MemLoc("x"));
+  let eax = Operand::Reg(Reg::Eax);
+  let ebx = Operand::Reg(Reg::Ebx);
+  let one = Operand::Imm(Value(1));
+
+  let p0 = Proc(0);
+  let i0 = vec![Inst::Mov(eax, one), Inst::LockXadd(x, eax)];
+
+  let p1 = Proc(1);
+  let i1 = vec![Inst::Mov(ebx, one), Inst::LockXadd(x, ebx)];
+
+  let mut prog: BTreeMap<Proc, Vec<Inst>> = BTreeMap::new();
+  prog.insert(p0, i0);
+  prog.insert(p1, i1);
+
+  // every processor draws a distinct ticket.
+  let pred = Pred::And(vec![
+    Pred::MemLoc(MemLoc("x"), Value(2)),
+    Pred::Or(vec![
+      Pred::And(vec![
+        Pred::Reg(p0, Reg::Eax, Value(0)),
+        Pred::Reg(p1, Reg::Ebx, Value(1)),
+      ]),
+      Pred::And(vec![
+        Pred::Reg(p0, Reg::Eax, Value(1)),
+        Pred::Reg(p1, Reg::Ebx, Value(0)),
+      ]),
+    ]),
+  ]);
+
+  assert!(
+    litmus(
+      "lock_xadd",
+      &Prog(prog),
+      State::new(&vec![p0, p1]),
+      &pred,
+      PredType::Required,
+    ).unwrap()
+      .verdict
+  );
+}
+
+#[test]
+fn lock_cmpxchg() {
+  let test = parse(
+    r#"
+X86 lock-cmpxchg
+(* only one processor acquires the lock, the loser sees the winner's id *)
+{ 0:ECX=1; 1:ECX=2; }
+ P0                 | P1                 ;
+ LOCK CMPXCHG [x],ECX | LOCK CMPXCHG [x],ECX ;
+forall ((x=1 /\ 0:EAX=0 /\ 1:EAX=1) \/ (x=2 /\ 0:EAX=2 /\ 1:EAX=0))
+"#,
+  ).unwrap();
+
+  assert!(
+    litmus(&test.name, &test.prog, test.init, &test.pred, test.pred_type)
+      .unwrap()
+      .verdict
+  );
+}
+
+#[test]
+fn lock_cmpxchg_failure_fences() {
+  let test = parse(
+    r#"
+X86 lock-cmpxchg-sb
+(* a failing locked cmpxchg still drains the store buffer *)
+{ 0:EAX=1; 1:EAX=1; }
+ P0                   | P1                   ;
+ MOV [x],$1           | MOV [y],$1           ;
+ LOCK CMPXCHG [z],EAX | LOCK CMPXCHG [z],EAX ;
+ MOV EBX,[y]          | MOV EBX,[x]          ;
+~exists (0:EBX=0 /\ 1:EBX=0)
+"#,
+  ).unwrap();
+
+  assert!(
+    litmus(&test.name, &test.prog, test.init, &test.pred, test.pred_type)
+      .unwrap()
+      .verdict
+  );
+}
+
+#[test]
+fn lock_register_destination() {
+  let err = parse("X86 T\n{}\nP0 ;\nLOCK MOV [x],$1 ;\nexists (x=1)").err();
+  assert_eq!(err.unwrap().message, "`LOCK` cannot prefix `MOV`");
+
+  let test = parse("X86 T\n{}\nP0 ;\nLOCK INC EAX ;\nexists (0:EAX=1)");
+  let test = test.unwrap();
+  let result =
+    litmus(&test.name, &test.prog, test.init, &test.pred, test.pred_type);
+  assert_eq!(
+    result.err().unwrap().to_string(),
+    "P0, instruction 0 `lock inc eax`: unsupported operands"
+  );
+}
@@ -0,0 +1,137 @@
+extern crate x86_litmus;
+
+mod common;
+
+use common::check;
+use std::fmt;
+use std::sync::Arc;
+use x86_litmus::ast::{CoreProg, MemLoc, Proc, Value};
+use x86_litmus::config::Config;
+use x86_litmus::model::{MemoryModel, ModelState, Sc, Transition, Tso};
+use x86_litmus::parser::parse;
+use x86_litmus::state::State;
+use x86_litmus::state_graph;
+use x86_litmus::trace::Rule;
+
+fn outcomes(text: &str, model: &'static dyn MemoryModel) -> usize {
+  let result = check(text, &Config { model, ..Config::default() });
+  assert!(result.verdict);
+  result.outcomes.len()
+}
+
+#[test]
+fn sb_under_sc() {
+  let sb = r#"
+X86 SB
+{ x=0; y=0; }
+ P0          | P1          ;
+ MOV [x],$1  | MOV [y],$1  ;
+ MOV EAX,[y] | MOV EBX,[x] ;
+~exists (0:EAX=0 /\ 1:EBX=0)
+"#;
+
+  assert_eq!(outcomes(sb, &Sc), 3);
+
+  let sb = sb.replace("~exists", "exists");
+  assert_eq!(outcomes(&sb, &Tso), 4);
+}
+
+#[test]
+fn lock_under_sc() {
+  let text = r#"
+X86 lock-inc
+{ x=0; }
+ P0             | P1             ;
+ LOCK INC [x]   | MOV EAX,[x]    ;
+                | LOCK INC [x]   ;
+forall (x=2)
+"#;
+
+  assert_eq!(outcomes(text, &Sc), 2);
+  assert_eq!(outcomes(text, &Tso), 2);
+}
+
+// SC for programs of reads and writes, counting the writes of each
+// processor in its own model state.
+struct Counting;
+
+#[derive(PartialEq, Eq, Hash, Debug)]
+struct Writes(usize);
+
+impl ModelState for Writes {
+  fn pending(&self) -> usize {
+    0
+  }
+
+  fn forward(&self, _: MemLoc) -> Option<Value> {
+    None
+  }
+}
+
+impl fmt::Display for Writes {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{} writes", self.0)
+  }
+}
+
+fn sc(rule: Rule) -> Transition {
+  Sc.rules().iter().find(|&&(sc_rule, _)| sc_rule == rule).unwrap().1
+}
+
+fn read(processor: Proc, prog: &CoreProg, state: &State) -> Option<State> {
+  sc(Rule::Read)(processor, prog, state)
+}
+
+fn write(processor: Proc, prog: &CoreProg, state: &State) -> Option<State> {
+  let mut nstate = sc(Rule::Write)(processor, prog, state)?;
+  let proc_state = nstate.procs.get_mut(&processor).unwrap();
+  let writes = proc_state.model.downcast_ref::<Writes>().unwrap().0;
+  proc_state.model = Arc::new(Writes(writes + 1));
+  Some(nstate)
+}
+
+static COUNTING: [(Rule, Transition); 2] =
+  [(Rule::Read, read), (Rule::Write, write)];
+
+impl MemoryModel for Counting {
+  fn name(&self) -> &'static str {
+    "counting"
+  }
+
+  fn rules(&self) -> &'static [(Rule, Transition)] {
+    &COUNTING
+  }
+
+  fn is_local(&self, _: Rule) -> bool {
+    false
+  }
+
+  fn init(&self) -> Arc<dyn ModelState> {
+    Arc::new(Writes(0))
+  }
+}
+
+#[test]
+fn model_state() {
+  let text = r#"
+X86 writes
+{ x=0; y=0; }
+ P0          | P1          ;
+ MOV [x],$1  | MOV [y],$1  ;
+ MOV [x],$2  | MOV EBX,[x] ;
+forall (x=2)
+"#;
+  let config = Config { model: &Counting, ..Config::default() };
+  let counting = check(text, &config);
+  let sc = check(text, &Config { model: &Sc, ..Config::default() });
+
+  assert!(counting.verdict);
+  assert_eq!(counting.outcomes, sc.outcomes);
+  assert_eq!(counting.states, sc.states);
+
+  let test = parse(text).unwrap();
+  let dot = state_graph(&test.name, &test.prog, test.init, &config)
+    .unwrap()
+    .to_dot(&test.name);
+  assert!(dot.contains("P0 done [2 writes]\\nP1 done ebx=1 [1 writes]"));
+}
@@ -35,5 +35,5 @@ fn n4b() {
     State::new(&vec![p0, p1]),
     &pred,
     PredType::Forbidden,
-  ));
+  ).unwrap().verdict);
 }
@@ -37,5 +37,5 @@ fn n6() {
     State::new(&vec![p0, p1]),
     &pred,
     PredType::Allowed,
-  ));
+  ).unwrap().verdict);
 }
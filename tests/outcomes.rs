@@ -0,0 +1,57 @@
+extern crate x86_litmus;
+
+use std::collections::BTreeMap;
+use x86_litmus::ast::{Inst, Pred, PredType, Prog};
+use x86_litmus::ast::{Proc, Operand, Reg, Value, MemLoc};
+use x86_litmus::litmus;
+use x86_litmus::state::State;
+
+#[test]
+fn outcomes() {
+  let x = Operand::MemLoc(MemLoc("x"));
+  let y = Operand::MemLoc(MemLoc("y"));
+  let eax = Operand::Reg(Reg::Eax);
+  let ebx = Operand::Reg(Reg::Ebx);
+  let one = Operand::Imm(Value(1));
+
+  let p0 = Proc(0);
+  let i0 = vec![Inst::Mov(x, one), Inst::Mov(eax, y)];
+
+  let p1 = Proc(1);
+  let i1 = vec![Inst::Xchg(y, ebx), Inst::Mov(ebx, x)];
+
+  let mut prog: BTreeMap<Proc, Vec<Inst>> = BTreeMap::new();
+  prog.insert(p0, i0);
+  prog.insert(p1, i1);
+
+  let pred = Pred::And(vec![
+    Pred::Reg(p0, Reg::Eax, Value(0)),
+    Pred::Reg(p1, Reg::Ebx, Value(0)),
+  ]);
+
+  let result = litmus(
+    "outcomes",
+    &Prog(prog),
+    State::new(&vec![p0, p1]),
+    &pred,
+    PredType::Forbidden,
+  ).unwrap();
+
+  // the internal register used by xchg does not leak into outcomes.
+  let outcomes: Vec<String> = result
+    .outcomes
+    .iter()
+    .map(|terminal| terminal.to_string())
+    .collect();
+  assert_eq!(
+    outcomes,
+    vec![
+      "0:eax=0; 1:ebx=0; x=1; y=0;",
+      "0:eax=0; 1:ebx=1; x=1; y=0;",
+    ]
+  );
+  assert_eq!(result.matching, vec![result.outcomes[0].clone()]);
+  assert!(result.states > result.outcomes.len());
+  assert!(result.transitions >= result.states - 1);
+  assert!(!result.verdict);
+}
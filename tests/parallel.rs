@@ -0,0 +1,67 @@
+extern crate x86_litmus;
+
+mod common;
+
+use common::{check, litmus_files};
+use std::fs;
+use x86_litmus::check::CheckResult;
+use x86_litmus::config::Config;
+use x86_litmus::model::{MemoryModel, Sc, Tso};
+
+#[test]
+fn same_as_sequential() {
+  let models: [&'static dyn MemoryModel; 2] = [&Tso, &Sc];
+
+  for text in litmus_files() {
+    for &model in &models {
+      for &por in &[false, true] {
+        let config = Config { model, por, ..Config::default() };
+        let sequential = check(&text, &config);
+        let parallel = check(&text, &Config { threads: 4, ..config });
+
+        let name = &parallel.name;
+        assert_eq!(sequential.outcomes, parallel.outcomes, "{}", name);
+        assert_eq!(sequential.verdict, parallel.verdict);
+        assert_eq!(sequential.states, parallel.states);
+        assert_eq!(sequential.transitions, parallel.transitions);
+      }
+    }
+  }
+}
+
+// the outcomes and states of IRIW do not depend on the number of workers.
+#[test]
+fn iriw() {
+  let text = fs::read_to_string("litmus/iriw.litmus").unwrap();
+  let sequential = check(&text, &Config::default());
+  for &threads in &[2, 3, 8] {
+    let parallel = check(&text, &Config { threads, ..Config::default() });
+
+    assert_eq!(sequential.outcomes, parallel.outcomes);
+    assert_eq!(parallel.outcomes.len(), 15);
+    assert_eq!(parallel.states, 164);
+  }
+}
+
+#[test]
+fn shortest_witness() {
+  let text = fs::read_to_string("litmus/sb.litmus").unwrap();
+  let sequential = check(&text, &Config::default());
+  let parallel = check(&text, &Config { threads: 4, ..Config::default() });
+
+  let length =
+    |result: &CheckResult| result.witness.as_ref().unwrap().1.0.len();
+  assert_eq!(length(&sequential), length(&parallel));
+}
+
+#[test]
+fn bounded() {
+  let text = fs::read_to_string("litmus/spinlock.litmus").unwrap();
+  let config = Config { max_iterations: Some(2), ..Config::default() };
+  let sequential = check(&text, &config);
+  let parallel = check(&text, &Config { threads: 3, ..config });
+
+  assert_eq!(sequential.outcomes, parallel.outcomes);
+  assert_eq!(sequential.truncated, parallel.truncated);
+  assert!(!parallel.is_complete());
+}
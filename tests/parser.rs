@@ -0,0 +1,94 @@
+extern crate x86_litmus;
+
+use x86_litmus::litmus;
+use x86_litmus::parser::parse;
+
+#[test]
+fn parse_sb() {
+  let test = parse(
+    r#"
+X86 SB
+"Fre PodWR Fre PodWR"
+{ x=0; y=0; }
+ P0          | P1          ;
+ MOV [x],$1  | MOV [y],$1  ;
+ MOV EAX,[y] | MOV EAX,[x] ;
+exists (0:EAX=0 /\ 1:EAX=0)
+"#,
+  ).unwrap();
+
+  assert_eq!(test.name, "SB");
+  assert_eq!(test.prog.0.len(), 2);
+  assert!(litmus(
+    &test.name,
+    &test.prog,
+    test.init,
+    &test.pred,
+    test.pred_type,
+  ).unwrap().verdict);
+}
+
+#[test]
+fn parse_init_and_comments() {
+  let test = parse(
+    r#"
+X86 ex10 (* xchg with a non-zero register *)
+{
+  0:EAX=1;
+}
+ P0          | P1          ;
+ XCHG [x],EAX | MOV EBX,[y] ;
+ MOV [y],$1  | MOV ECX,[x] ;
+~exists (1:EBX=1 /\ 1:ECX=0)
+"#,
+  ).unwrap();
+
+  assert!(litmus(
+    &test.name,
+    &test.prog,
+    test.init,
+    &test.pred,
+    test.pred_type,
+  ).unwrap().verdict);
+}
+
+#[test]
+fn parse_errors() {
+  let error = parse("ARM MP\n{}\nP0;\nMOV [x],$1;\nexists (x=1)").err();
+  assert_eq!(error.unwrap().line, 1);
+
+  let error = parse("X86 T\n{ x=0; }\nP0 ;\nMOVE [x],$1 ;\nexists (x=1)").err();
+  let error = error.unwrap();
+  assert_eq!(error.line, 4);
+  assert_eq!(error.message, "unknown instruction `MOVE`");
+
+  let error = parse("X86 T\n{}\nP0 ;\nMOV [x],$1 ;\nexists (x=1 \\/ x)");
+  let error = error.err().unwrap();
+  assert_eq!(error.line, 5);
+  assert_eq!(error.message, "expected comparison, found `)`");
+
+  let error = parse("X86 T\n{}\nP0 ;\nMOV [x],$1 ;\n").err();
+  assert_eq!(error.unwrap().message, "missing final condition");
+
+  let error = parse("X86 T\n{}\nP0 ;\nMOV [x],$1 ;\nexists (5:EAX=1)");
+  let error = error.err().unwrap();
+  assert_eq!(error.line, 5);
+  assert_eq!(error.message, "unknown processor `P5`");
+
+  let error = parse("X86 T\n{}\nP0 ;\nMOV [x],$1 ;\nforall ~(x=1:EAX)");
+  assert_eq!(error.err().unwrap().message, "unknown processor `P1`");
+}
+
+#[test]
+fn print_and_parse() {
+  let text = r#"X86 ex8
+{ 0:eax=1; 1:ebx=1; }
+ P0            | P1            | P2           ;
+ xchg [x], eax | xchg [y], ebx | mov eax, [x] ;
+               |               | mov ebx, [y] ;
+~exists (2:eax=1 /\ 2:ebx=0)"#;
+  let test = parse(text).unwrap();
+
+  assert_eq!(test.to_string(), text);
+  assert_eq!(parse(&test.to_string()).unwrap().to_string(), text);
+}
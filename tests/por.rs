@@ -0,0 +1,73 @@
+extern crate x86_litmus;
+
+mod common;
+
+use common::{check, litmus_files};
+use std::fs;
+use x86_litmus::config::Config;
+use x86_litmus::model::{MemoryModel, Sc, Tso};
+
+fn por(model: &'static dyn MemoryModel, por: bool) -> Config {
+  Config { model, por, ..Config::default() }
+}
+
+#[test]
+fn same_outcomes() {
+  let models: [&'static dyn MemoryModel; 2] = [&Tso, &Sc];
+
+  for text in litmus_files() {
+    for &model in &models {
+      let full = check(&text, &por(model, false));
+      let reduced = check(&text, &por(model, true));
+
+      assert_eq!(full.outcomes, reduced.outcomes, "{}", full.name);
+      assert_eq!(full.verdict, reduced.verdict);
+      assert!(reduced.states <= full.states);
+    }
+  }
+}
+
+#[test]
+fn fewer_states() {
+  let text = fs::read_to_string("litmus/iriw.litmus").unwrap();
+  let full = check(&text, &por(&Tso, false));
+  let reduced = check(&text, &por(&Tso, true));
+
+  assert_eq!(full.outcomes, reduced.outcomes);
+  assert_eq!(full.states, 164);
+  assert_eq!(reduced.states, 99);
+}
+
+// buffering a write is local under x86-TSO, while under SC every step of SB
+// touches memory.
+#[test]
+fn store_buffering() {
+  let text = fs::read_to_string("litmus/sb.litmus").unwrap();
+  let states = |model| {
+    let full = check(&text, &por(model, false));
+    let reduced = check(&text, &por(model, true));
+    assert_eq!(full.outcomes, reduced.outcomes);
+    (full.states, reduced.states)
+  };
+
+  assert_eq!(states(&Tso), (34, 27));
+  assert_eq!(states(&Sc), (13, 13));
+}
+
+#[test]
+fn unwritten_reads() {
+  // nobody writes z, so reading it commutes with every other step.
+  let text = r#"
+X86 unwritten
+{ x=0; z=0; }
+ P0          | P1          ;
+ MOV [x],$1  | MOV EBX,[z] ;
+ MOV EAX,[z] | MOV ECX,[x] ;
+exists (1:ECX=0)
+"#;
+  let full = check(text, &por(&Tso, false));
+  let reduced = check(text, &por(&Tso, true));
+
+  assert_eq!(full.outcomes, reduced.outcomes);
+  assert_eq!((full.states, reduced.states), (17, 8));
+}
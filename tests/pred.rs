@@ -0,0 +1,121 @@
+extern crate x86_litmus;
+
+use std::collections::BTreeMap;
+use x86_litmus::ast::{Inst, Pred, PredType, Prog, Expr, Rel};
+use x86_litmus::ast::{Proc, Operand, Reg, Value, MemLoc};
+use x86_litmus::litmus;
+use x86_litmus::parser::parse;
+use x86_litmus::state::State;
+
+fn check(condition: &str) -> bool {
+  let text = format!(
+    "X86 pred
+{{ x=0; y=0; }}
+ P0          | P1          ;
+ MOV [x],$1  | MOV [y],$1  ;
+ MOV EAX,[y] | MOV EBX,[x] ;
+ MOV ECX,$1  |             ;
+{}",
+    condition
+  );
+  let test = parse(&text).unwrap();
+
+  let result =
+    litmus(&test.name, &test.prog, test.init, &test.pred, test.pred_type);
+
+  result.unwrap().verdict
+}
+
+#[test]
+fn pred_connectives() {
+  assert!(check("exists (0:EAX=0 /\\ (1:EBX=0 \\/ 1:EBX=2))"));
+  assert!(!check("exists (0:EAX=2 \\/ 1:EBX=2)"));
+  assert!(check("forall (0:EAX=0 => 1:EBX=1 \\/ 1:EBX=0)"));
+  assert!(!check("forall (0:EAX=0 => 1:EBX=1)"));
+  assert!(check("forall ~(x=0 \\/ y=0)"));
+  assert!(check("forall true"));
+  assert!(check("~exists false"));
+}
+
+#[test]
+fn pred_comparisons() {
+  assert!(check("exists (0:EAX != 1:EBX)"));
+  assert!(check("forall (0:EAX <= 0:ECX /\\ 0:ECX = x)"));
+  assert!(check("~exists (0:EAX > y)"));
+  assert!(check("forall (0:EAX < 2 /\\ 1:EBX >= 0)"));
+  assert!(!check("forall (0:EAX = 1:EBX)"));
+}
+
+#[test]
+fn pred_display() {
+  let pred = Pred::Implies(
+    Box::new(Pred::Not(Box::new(Pred::Or(vec![
+      Pred::Reg(Proc(0), Reg::Eax, Value(1)),
+      Pred::True,
+    ])))),
+    Box::new(Pred::Cmp(
+      Expr::Reg(Proc(1), Reg::Ebx),
+      Rel::Le,
+      Expr::MemLoc(MemLoc("x")),
+    )),
+  );
+
+  assert_eq!(pred.to_string(), "(~(0:eax=1 \\/ true) => 1:ebx<=x)");
+}
+
+#[test]
+fn pred_register_to_register() {
+  let x = Operand::MemLoc(MemLoc("x"));
+  let eax = Operand::Reg(Reg::Eax);
+  let ebx = Operand::Reg(Reg::Ebx);
+  let one = Operand::Imm(Value(1));
+
+  let p0 = Proc(0);
+  let i0 = vec![Inst::Mov(x, one)];
+
+  let p1 = Proc(1);
+  let i1 = vec![Inst::Mov(eax, x), Inst::Mov(ebx, x)];
+
+  let mut prog: BTreeMap<Proc, Vec<Inst>> = BTreeMap::new();
+  prog.insert(p0, i0);
+  prog.insert(p1, i1);
+
+  // reads of the same location never go backwards.
+  let pred = Pred::Cmp(
+    Expr::Reg(p1, Reg::Eax),
+    Rel::Le,
+    Expr::Reg(p1, Reg::Ebx),
+  );
+
+  assert!(
+    litmus(
+      "pred_register_to_register",
+      &Prog(prog),
+      State::new(&vec![p0, p1]),
+      &pred,
+      PredType::Required,
+    ).unwrap().verdict
+  );
+}
+
+#[test]
+fn pred_unknown_processor() {
+  let eax = Operand::Reg(Reg::Eax);
+  let one = Operand::Imm(Value(1));
+
+  let mut prog: BTreeMap<Proc, Vec<Inst>> = BTreeMap::new();
+  prog.insert(Proc(0), vec![Inst::Mov(eax, one)]);
+
+  // conditions built by hand are not checked, so the register reads as 0.
+  let pred = Pred::Reg(Proc(5), Reg::Eax, Value(0));
+
+  assert!(
+    litmus(
+      "pred_unknown_processor",
+      &Prog(prog),
+      State::new(&vec![Proc(0)]),
+      &pred,
+      PredType::Required,
+    ).unwrap().verdict
+  );
+}
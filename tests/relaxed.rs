@@ -0,0 +1,51 @@
+extern crate x86_litmus;
+
+use x86_litmus::check::Relaxed;
+use x86_litmus::config::Config;
+use x86_litmus::parser::parse;
+use x86_litmus::relaxed;
+
+fn report(text: &str) -> Relaxed {
+  let test = parse(text).unwrap();
+  relaxed(&test.name, &test.prog, test.init, &Config::default()).unwrap()
+}
+
+#[test]
+fn sb_relaxed() {
+  let report = report(
+    r#"
+X86 SB
+{ x=0; y=0; }
+ P0          | P1          ;
+ MOV [x],$1  | MOV [y],$1  ;
+ MOV EAX,[y] | MOV EBX,[x] ;
+exists (0:EAX=0 /\ 1:EBX=0)
+"#,
+  );
+
+  assert_eq!(report.sc.len(), 3);
+  assert_eq!(report.outcomes.len(), 4);
+  assert_eq!(report.relaxed.len(), 1);
+  assert_eq!(
+    report.relaxed[0].to_string(),
+    "0:eax=0; 1:ebx=0; x=1; y=1;"
+  );
+}
+
+#[test]
+fn fenced_sb_not_relaxed() {
+  let report = report(
+    r#"
+X86 SB+mfences
+{ x=0; y=0; }
+ P0          | P1          ;
+ MOV [x],$1  | MOV [y],$1  ;
+ MFENCE      | MFENCE      ;
+ MOV EAX,[y] | MOV EBX,[x] ;
+~exists (0:EAX=0 /\ 1:EBX=0)
+"#,
+  );
+
+  assert_eq!(report.sc, report.outcomes);
+  assert!(report.relaxed.is_empty());
+}
@@ -35,5 +35,5 @@ fn sb() {
     State::new(&vec![p0, p1]),
     &pred,
     PredType::Allowed,
-  ));
+  ).unwrap().verdict);
 }
@@ -0,0 +1,116 @@
+extern crate x86_litmus;
+
+use std::collections::BTreeMap;
+use x86_litmus::ast::{Inst, CoreInst, Pred, PredType, Prog};
+use x86_litmus::ast::{Proc, Operand, Reg, Value, MemLoc};
+use x86_litmus::litmus;
+use x86_litmus::state::State;
+use x86_litmus::trace::{Rule, Step};
+
+#[test]
+fn witness() {
+  let x = Operand::MemLoc(MemLoc("x"));
+  let y = Operand::MemLoc(MemLoc("y"));
+  let eax = Operand::Reg(Reg::Eax);
+  let ebx = Operand::Reg(Reg::Ebx);
+  let one = Operand::Imm(Value(1));
+
+  let p0 = Proc(0);
+  let i0 = vec![Inst::Mov(x, one), Inst::Mov(eax, y)];
+
+  let p1 = Proc(1);
+  let i1 = vec![Inst::Mov(y, one), Inst::Mov(ebx, x)];
+
+  let mut prog: BTreeMap<Proc, Vec<Inst>> = BTreeMap::new();
+  prog.insert(p0, i0);
+  prog.insert(p1, i1);
+
+  let pred = Pred::And(vec![
+    Pred::Reg(p0, Reg::Eax, Value(0)),
+    Pred::Reg(p1, Reg::Ebx, Value(0)),
+  ]);
+
+  let result = litmus(
+    "witness",
+    &Prog(prog),
+    State::new(&vec![p0, p1]),
+    &pred,
+    PredType::Forbidden,
+  ).unwrap();
+  assert!(!result.verdict);
+
+  // both loads happen before either write leaves its store buffer.
+  let (terminal, trace) = result.witness.unwrap();
+  assert!(terminal.satisfy(&pred));
+  assert_eq!(
+    trace.0,
+    vec![
+      Step {
+        processor: p0,
+        rule: Rule::Write,
+        inst: Some(CoreInst::Write2(MemLoc("x"), Value(1))),
+      },
+      Step {
+        processor: p0,
+        rule: Rule::Read,
+        inst: Some(CoreInst::Read(Reg::Eax, MemLoc("y"))),
+      },
+      Step {
+        processor: p1,
+        rule: Rule::Write,
+        inst: Some(CoreInst::Write2(MemLoc("y"), Value(1))),
+      },
+      Step {
+        processor: p1,
+        rule: Rule::Read,
+        inst: Some(CoreInst::Read(Reg::Ebx, MemLoc("x"))),
+      },
+      Step {
+        processor: p0,
+        rule: Rule::Tau,
+        inst: None,
+      },
+      Step {
+        processor: p1,
+        rule: Rule::Tau,
+        inst: None,
+      },
+    ]
+  );
+}
+
+#[test]
+fn witness_required() {
+  let x = Operand::MemLoc(MemLoc("x"));
+  let eax = Operand::Reg(Reg::Eax);
+  let one = Operand::Imm(Value(1));
+  let two = Operand::Imm(Value(2));
+
+  let p0 = Proc(0);
+  let i0 = vec![Inst::Mov(x, one), Inst::Mov(eax, x)];
+
+  let p1 = Proc(1);
+  let i1 = vec![Inst::Mov(x, two)];
+
+  let mut prog: BTreeMap<Proc, Vec<Inst>> = BTreeMap::new();
+  prog.insert(p0, i0);
+  prog.insert(p1, i1);
+
+  let pred = Pred::MemLoc(MemLoc("x"), Value(2));
+
+  let result = litmus(
+    "witness_required",
+    &Prog(prog),
+    State::new(&vec![p0, p1]),
+    &pred,
+    PredType::Required,
+  ).unwrap();
+  assert!(!result.verdict);
+
+  // the witness is an outcome violating the predicate, ending with the write
+  // of 1 reaching memory last.
+  let (terminal, trace) = result.witness.unwrap();
+  assert!(!terminal.satisfy(&pred));
+  let last = trace.0.last().unwrap();
+  assert_eq!((last.processor, last.rule), (p0, Rule::Tau));
+}